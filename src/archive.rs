@@ -1,4 +1,5 @@
 use std::{
+    collections::{BTreeMap, HashMap},
     error::Error,
     fs,
     path::{Path, PathBuf},
@@ -8,6 +9,13 @@ use serde::{Deserialize, Serialize};
 
 use crate::collector::Collector;
 use crate::movie::Movie;
+use crate::query::Query;
+use crate::series::Series;
+use crate::session::Session;
+use crate::sort::SortOrder;
+use crate::tracks::TrackChoices;
+use crate::verifier::Integrity;
+use crate::watch_later::WatchLater;
 
 #[cfg(debug_assertions)]
 const SAVE_FILE: &str = ".debug_cineteca.json";
@@ -17,6 +25,23 @@ const SAVE_FILE: &str = ".movies.json";
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Archive {
     pub movies: Vec<Movie>,
+    #[serde(default)]
+    pub series: Vec<Series>,
+    /// Files waiting to be converted to the transcode profile, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    transcode_queue: Vec<PathBuf>,
+    /// Main files of the movies and episodes to play one after another
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    play_queue: Vec<PathBuf>,
+    /// Names of the movies last picked at random, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    recent_picks: Vec<String>,
+    /// How the list was last sorted
+    #[serde(default)]
+    sort: SortOrder,
+    /// Queries saved by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    smart_lists: BTreeMap<String, String>,
     hash: u64,
     save_path: PathBuf,
     path: PathBuf,
@@ -24,13 +49,40 @@ pub struct Archive {
 
 impl Archive {
     pub fn init(path: &Path) -> Self {
-        let save_path = path.join(SAVE_FILE);
-        Self::load_saved(&save_path).unwrap_or_else(|_| Self::new(path, &save_path))
+        Self::load(path).unwrap_or_else(|| Self::new(path, &path.join(SAVE_FILE)))
     }
 
-    pub fn update(&mut self, new_movies: Vec<Movie>, new_hash: u64) {
+    /// Library saved in `path`, if it was opened before. Unlike `init`, it is
+    /// not collected when there is none.
+    pub fn load(path: &Path) -> Option<Self> {
+        Self::load_saved(&path.join(SAVE_FILE)).ok()
+    }
+
+    pub fn update(
+        &mut self,
+        mut new_movies: Vec<Movie>,
+        mut new_series: Vec<Series>,
+        new_hash: u64,
+    ) {
         if new_hash != self.hash {
+            let old_movies: HashMap<&Path, &Movie> = self
+                .movies
+                .iter()
+                .flat_map(|movie| movie.version_paths().map(move |path| (path, movie)))
+                .collect();
+            for movie in &mut new_movies {
+                let old: Vec<&Movie> = movie
+                    .version_paths()
+                    .filter_map(|path| old_movies.get(path).copied())
+                    .collect();
+                movie.restore(&old);
+            }
+            for series in &mut new_series {
+                series.restore_watched(&self.series);
+            }
+
             self.movies = new_movies;
+            self.series = new_series;
             self.hash = new_hash;
         }
     }
@@ -41,8 +93,8 @@ impl Archive {
             .unwrap()
     }
 
-    pub fn get_path(&self, name: &str) -> &Path {
-        self.movies.get(self.get_index(name)).unwrap().path()
+    pub fn get_movie(&self, name: &str) -> &Movie {
+        self.movies.get(self.get_index(name)).unwrap()
     }
 
     pub fn toggle_watched(&mut self, name: &str) {
@@ -50,11 +102,254 @@ impl Archive {
         self.movies.get_mut(index).unwrap().toggle_watched();
     }
 
+    pub fn set_integrity(&mut self, name: &str, integrity: Integrity) {
+        if let Ok(index) = self
+            .movies
+            .binary_search_by_key(&name, |movie| movie.name())
+        {
+            self.movies[index].set_integrity(integrity);
+        }
+    }
+
+    pub fn set_credits_start(&mut self, name: &str, credits_start: u64) {
+        if let Ok(index) = self
+            .movies
+            .binary_search_by_key(&name, |movie| movie.name())
+        {
+            self.movies[index].set_credits_start(credits_start);
+        }
+    }
+
+    pub fn set_tracks(&mut self, name: &str, tracks: TrackChoices) {
+        if let Ok(index) = self
+            .movies
+            .binary_search_by_key(&name, |movie| movie.name())
+        {
+            self.movies[index].set_tracks(tracks);
+        }
+    }
+
+    pub fn set_resume_position(&mut self, name: &str, resume_position: Option<u64>) {
+        if let Ok(index) = self
+            .movies
+            .binary_search_by_key(&name, |movie| movie.name())
+        {
+            self.movies[index].set_resume_position(resume_position);
+        }
+    }
+
+    /// Takes the positions mpv saved for movies and episodes quit before
+    /// the end, unless they were watched or their position was saved here
+    /// since. Movies split into parts are left out, as mpv saves a position
+    /// per part.
+    pub fn import_watch_later(&mut self, watch_later: &WatchLater) {
+        for movie in &mut self.movies {
+            if movie.is_disc() || movie.num_parts() > 1 {
+                continue;
+            }
+            let Some(path) = movie.paths().next() else {
+                continue;
+            };
+            let after = movie.since_watched().max(movie.resume_saved());
+            if let Some(position) = watch_later.position(path, after) {
+                movie.set_resume_position(Some(position));
+            }
+        }
+
+        for episode in self.series.iter_mut().flat_map(Series::episodes_mut) {
+            let after = episode.since_watched().max(episode.resume_saved());
+            if let Some(position) = watch_later.position(episode.path(), after) {
+                episode.set_resume_position(Some(position));
+            }
+        }
+    }
+
+    pub fn transcode_queue(&self) -> &[PathBuf] {
+        &self.transcode_queue
+    }
+
+    /// Queues the preferred version of a movie for conversion, returning
+    /// false if it is already queued.
+    pub fn queue_transcode(&mut self, name: &str) -> bool {
+        let path = self.get_movie(name).paths().next().cloned();
+        match path {
+            Some(path) if !self.transcode_queue.contains(&path) => {
+                self.transcode_queue.push(path);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Removes a file from the queue, linking the converted copy to its
+    /// movie if there is one.
+    pub fn finish_transcode(&mut self, path: &Path, transcoded: Option<PathBuf>) {
+        self.transcode_queue.retain(|queued| queued != path);
+
+        if let Some(transcoded) = transcoded
+            && let Some(movie) = self
+                .movies
+                .iter_mut()
+                .find(|movie| movie.version_paths().any(|version| version == path))
+        {
+            movie.set_transcoded(path, transcoded);
+        }
+    }
+
+    pub fn play_queue(&self) -> &[PathBuf] {
+        &self.play_queue
+    }
+
+    /// Adds a file to the end of the play queue, returning false if it is
+    /// already queued.
+    pub fn enqueue(&mut self, path: PathBuf) -> bool {
+        if self.play_queue.contains(&path) {
+            return false;
+        }
+        self.play_queue.push(path);
+        true
+    }
+
+    pub fn dequeue(&mut self, path: &Path) {
+        self.play_queue.retain(|queued| queued != path);
+    }
+
+    /// Swaps a queued file with the one before it (or after it, if `earlier`
+    /// is false), returning its new index.
+    pub fn move_queued(&mut self, index: usize, earlier: bool) -> usize {
+        let other = if earlier {
+            index.checked_sub(1)
+        } else {
+            Some(index + 1).filter(|&other| other < self.play_queue.len())
+        };
+        match other {
+            Some(other) if index < self.play_queue.len() => {
+                self.play_queue.swap(index, other);
+                other
+            }
+            _ => index,
+        }
+    }
+
+    pub fn cycle_version(&mut self, name: &str) {
+        let index = self.get_index(name);
+        self.movies.get_mut(index).unwrap().cycle_version();
+    }
+
     pub fn set_watched(&mut self, name: &str) {
         let index = self.get_index(name);
         self.movies.get_mut(index).unwrap().set_watched();
     }
 
+    pub fn get_series(&self, name: &str) -> Option<&Series> {
+        self.series.iter().find(|series| series.name() == name)
+    }
+
+    pub fn toggle_episode_watched(&mut self, series: &str, path: &Path) {
+        if let Some(episode) = self
+            .get_series_mut(series)
+            .and_then(|s| s.episode_mut(path))
+        {
+            episode.toggle_watched();
+        }
+    }
+
+    pub fn set_episode_watched(&mut self, series: &str, path: &Path) {
+        if let Some(episode) = self
+            .get_series_mut(series)
+            .and_then(|s| s.episode_mut(path))
+        {
+            episode.set_watched();
+        }
+    }
+
+    pub fn set_episode_resume_position(
+        &mut self,
+        series: &str,
+        path: &Path,
+        resume_position: Option<u64>,
+    ) {
+        if let Some(episode) = self
+            .get_series_mut(series)
+            .and_then(|s| s.episode_mut(path))
+        {
+            episode.set_resume_position(resume_position);
+        }
+    }
+
+    pub fn add_session(&mut self, name: &str, session: Session) {
+        if let Ok(index) = self
+            .movies
+            .binary_search_by_key(&name, |movie| movie.name())
+        {
+            self.movies[index].add_session(session);
+        }
+    }
+
+    pub fn add_episode_session(&mut self, series: &str, path: &Path, session: Session) {
+        if let Some(episode) = self
+            .get_series_mut(series)
+            .and_then(|s| s.episode_mut(path))
+        {
+            episode.add_session(session);
+        }
+    }
+
+    pub fn set_rating(&mut self, name: &str, rating: Option<u8>) {
+        if let Ok(index) = self
+            .movies
+            .binary_search_by_key(&name, |movie| movie.name())
+        {
+            self.movies[index].set_rating(rating);
+        }
+    }
+
+    pub fn set_tags(&mut self, name: &str, tags: Vec<String>) {
+        if let Ok(index) = self
+            .movies
+            .binary_search_by_key(&name, |movie| movie.name())
+        {
+            self.movies[index].set_tags(tags);
+        }
+    }
+
+    pub fn recent_picks(&self) -> &[String] {
+        &self.recent_picks
+    }
+
+    /// Remembers a random pick, keeping only the last `keep` of them.
+    pub fn record_pick(&mut self, name: &str, keep: usize) {
+        self.recent_picks.retain(|pick| pick != name);
+        self.recent_picks.push(name.to_owned());
+        let excess = self.recent_picks.len().saturating_sub(keep);
+        self.recent_picks.drain(..excess);
+    }
+
+    pub const fn sort(&self) -> SortOrder {
+        self.sort
+    }
+
+    pub const fn sort_mut(&mut self) -> &mut SortOrder {
+        &mut self.sort
+    }
+
+    pub const fn smart_lists(&self) -> &BTreeMap<String, String> {
+        &self.smart_lists
+    }
+
+    /// Saves a query under a name, replacing any list with the same name.
+    pub fn save_smart_list(&mut self, name: &str, query: &Query) {
+        self.smart_lists.insert(name.to_owned(), query.to_string());
+    }
+
+    pub fn delete_smart_list(&mut self, name: &str) {
+        self.smart_lists.remove(name);
+    }
+
+    fn get_series_mut(&mut self, name: &str) -> Option<&mut Series> {
+        self.series.iter_mut().find(|series| series.name() == name)
+    }
+
     fn load_saved(save_path: &Path) -> Result<Self, Box<dyn Error>> {
         let json = fs::read_to_string(save_path)?;
         let movies = serde_json::from_str(&json)?;
@@ -68,9 +363,15 @@ impl Archive {
     }
 
     fn new(path: &Path, save_path: &Path) -> Self {
-        let (movies, hash) = Collector::collect(path);
+        let (movies, series, hash) = Collector::collect(path);
         Self {
             movies,
+            series,
+            transcode_queue: Vec::new(),
+            play_queue: Vec::new(),
+            recent_picks: Vec::new(),
+            sort: SortOrder::default(),
+            smart_lists: BTreeMap::new(),
             hash,
             save_path: save_path.to_path_buf(),
             path: path.to_path_buf(),
@@ -0,0 +1,270 @@
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use ffmpeg_next::{
+    Packet, codec, encoder,
+    format::{self, Pixel},
+    frame, media,
+    software::scaling,
+};
+
+use crate::{config::Config, movie::Movie};
+
+/// File names, without extension, of posters placed next to the movies.
+const POSTER_NAMES: [&str; 2] = ["poster", "folder"];
+const IMAGE_EXTENSIONS: [&str; 3] = ["jpg", "jpeg", "png"];
+const THUMBNAIL_WIDTH: u32 = 480;
+
+/// Poster of a movie, or a frame taken from it when there is none.
+pub struct Artwork;
+
+impl Artwork {
+    /// Image to show for the movie, if there is a poster or an up to date
+    /// thumbnail in the cache.
+    pub fn find(movie: &Movie) -> Option<PathBuf> {
+        Self::local_poster(movie).or_else(|| {
+            let thumbnail = Self::thumbnail_path(movie)?;
+            Self::is_fresh(&thumbnail, movie).then_some(thumbnail)
+        })
+    }
+
+    /// Whether a thumbnail has to be extracted for the movie. Discs are left
+    /// out since their menus rarely make for a good frame.
+    pub fn is_missing(movie: &Movie) -> bool {
+        !movie.is_disc() && Self::find(movie).is_none()
+    }
+
+    /// Grabs a frame at the configured offset and stores it in the cache.
+    pub fn extract(movie: &Movie) -> Result<PathBuf, Box<dyn Error>> {
+        let path = movie.paths().next().ok_or("Movie has no files")?;
+        let thumbnail = Self::thumbnail_path(movie).ok_or("No cache directory")?;
+        let offset = Config::get().thumbnail_offset.min(movie.length() / 2);
+
+        let frame = Self::grab_frame(path, offset)?;
+        let height = frame.height() * THUMBNAIL_WIDTH / frame.width().max(1);
+        let frame = Self::scale(&frame, THUMBNAIL_WIDTH, height.max(1))?;
+
+        if let Some(dir) = thumbnail.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&thumbnail, Self::encode_png(&frame)?)?;
+
+        Ok(thumbnail)
+    }
+
+    /// `poster.jpg`, `folder.jpg` or `<name>-poster.jpg` in the movie's
+    /// folder (or in the disc folder itself).
+    fn local_poster(movie: &Movie) -> Option<PathBuf> {
+        let path = movie.paths().next()?;
+        let dir = if path.is_dir() { path } else { path.parent()? };
+        let stem = path.file_stem()?.to_string_lossy();
+
+        POSTER_NAMES
+            .map(str::to_owned)
+            .into_iter()
+            .chain([format!("{stem}-poster")])
+            .flat_map(|name| IMAGE_EXTENSIONS.map(|ext| dir.join(format!("{name}.{ext}"))))
+            .find(|poster| poster.is_file())
+    }
+
+    fn thumbnail_path(movie: &Movie) -> Option<PathBuf> {
+        Config::cache_dir().map(|dir| dir.join("thumbnails").join(format!("{}.png", movie.id())))
+    }
+
+    /// A cached image is stale once the movie file is modified after it.
+    pub fn is_fresh(cached: &Path, movie: &Movie) -> bool {
+        let modified = |path: &Path| path.metadata().and_then(|m| m.modified()).ok();
+
+        modified(cached).is_some_and(|cached_at| {
+            movie
+                .paths()
+                .next()
+                .and_then(|path| modified(path))
+                .is_none_or(|source| cached_at >= source)
+        })
+    }
+
+    /// First frame of a video or image file.
+    pub fn decode(path: &Path) -> Result<frame::Video, Box<dyn Error>> {
+        Self::grab_frame(path, 0)
+    }
+
+    /// First frame decoded from the keyframe preceding `offset` seconds.
+    pub fn grab_frame(path: &Path, offset: u64) -> Result<frame::Video, Box<dyn Error>> {
+        let mut input = format::input(&path)?;
+
+        let (index, mut decoder) = {
+            let stream = input
+                .streams()
+                .best(media::Type::Video)
+                .ok_or("No video stream")?;
+            let decoder = codec::context::Context::from_parameters(stream.parameters())?
+                .decoder()
+                .video()?;
+            (stream.index(), decoder)
+        };
+
+        if offset > 0 {
+            let position = i64::try_from(offset)? * i64::from(ffmpeg_next::ffi::AV_TIME_BASE);
+            input.seek(position, ..position)?;
+        }
+
+        let mut packet = Packet::empty();
+        let mut frame = frame::Video::empty();
+        while packet.read(&mut input).is_ok() {
+            if packet.stream() != index {
+                continue;
+            }
+            decoder.send_packet(&packet)?;
+            if decoder.receive_frame(&mut frame).is_ok() {
+                return Ok(frame);
+            }
+        }
+
+        Err("No frame could be decoded".into())
+    }
+
+    /// Converts the frame to RGB at the given size.
+    pub fn scale(
+        frame: &frame::Video,
+        width: u32,
+        height: u32,
+    ) -> Result<frame::Video, ffmpeg_next::Error> {
+        let mut context = scaling::Context::get(
+            frame.format(),
+            frame.width(),
+            frame.height(),
+            Pixel::RGB24,
+            width,
+            height,
+            scaling::Flags::BILINEAR,
+        )?;
+
+        let mut scaled = frame::Video::empty();
+        context.run(frame, &mut scaled)?;
+        Ok(scaled)
+    }
+
+    /// Encodes an RGB frame as a PNG file.
+    pub fn encode_png(frame: &frame::Video) -> Result<Vec<u8>, Box<dyn Error>> {
+        let codec = encoder::find(codec::Id::PNG).ok_or("PNG encoder not available")?;
+        let mut encoder = codec::context::Context::new_with_codec(codec)
+            .encoder()
+            .video()?;
+        encoder.set_width(frame.width());
+        encoder.set_height(frame.height());
+        encoder.set_format(Pixel::RGB24);
+        encoder.set_time_base((1, 1));
+
+        let mut encoder = encoder.open()?;
+        encoder.send_frame(frame)?;
+        encoder.send_eof()?;
+
+        let mut packet = Packet::empty();
+        encoder.receive_packet(&mut packet)?;
+        Ok(packet.data().ok_or("Empty image")?.to_vec())
+    }
+}
+
+/// RGB image held in memory.
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    /// Black image of the given size.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width * height * 3],
+        }
+    }
+
+    /// Loads the image file at `path`, scaled to fit `width` by `height`
+    /// pixels, centered and padded with black.
+    pub fn load(path: &Path, width: usize, height: usize) -> Result<Self, Box<dyn Error>> {
+        let frame = Artwork::decode(path)?;
+        let (source_width, source_height) = (frame.width() as usize, frame.height() as usize);
+
+        let (fit_width, fit_height) = if source_width * height > source_height * width {
+            (width, source_height * width / source_width.max(1))
+        } else {
+            (source_width * height / source_height.max(1), height)
+        };
+        let scaled = Artwork::scale(
+            &frame,
+            u32::try_from(fit_width.max(1))?,
+            u32::try_from(fit_height.max(1))?,
+        )?;
+
+        let mut image = Self::new(width, height);
+        image.draw_frame(
+            &scaled,
+            (width - fit_width.min(width)) / 2,
+            (height - fit_height.min(height)) / 2,
+        );
+        Ok(image)
+    }
+
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    pub const fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Pixel data, row after row.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 3] {
+        let i = (y * self.width + x) * 3;
+        [self.pixels[i], self.pixels[i + 1], self.pixels[i + 2]]
+    }
+
+    /// Paints a rectangle, clipped to the image.
+    pub fn fill(&mut self, x: usize, y: usize, width: usize, height: usize, color: [u8; 3]) {
+        for row in y..(y + height).min(self.height) {
+            for column in x..(x + width).min(self.width) {
+                let i = (row * self.width + column) * 3;
+                self.pixels[i..i + 3].copy_from_slice(&color);
+            }
+        }
+    }
+
+    /// Copies an RGB frame with its top left corner at `x`, `y`, clipped to
+    /// the image.
+    pub fn draw_frame(&mut self, frame: &frame::Video, x: usize, y: usize) {
+        let stride = frame.stride(0);
+        let width = (frame.width() as usize).min(self.width.saturating_sub(x));
+        let height = (frame.height() as usize).min(self.height.saturating_sub(y));
+
+        for row in 0..height {
+            let source = &frame.data(0)[row * stride..row * stride + width * 3];
+            let start = ((y + row) * self.width + x) * 3;
+            self.pixels[start..start + source.len()].copy_from_slice(source);
+        }
+    }
+
+    pub fn to_png(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut frame = frame::Video::new(
+            Pixel::RGB24,
+            u32::try_from(self.width)?,
+            u32::try_from(self.height)?,
+        );
+        let stride = frame.stride(0);
+        for (y, row) in self.pixels.chunks(self.width * 3).enumerate() {
+            frame.data_mut(0)[y * stride..y * stride + row.len()].copy_from_slice(row);
+        }
+
+        Artwork::encode_png(&frame)
+    }
+}
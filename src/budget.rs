@@ -0,0 +1,153 @@
+use std::{error::Error, sync::OnceLock};
+
+use serde::{Deserialize, Serialize};
+use time::{Duration, OffsetDateTime, Time, UtcOffset};
+
+static LOCAL_OFFSET: OnceLock<UtcOffset> = OnceLock::new();
+
+/// Settings of the time budget mode.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct BudgetSettings {
+    /// Minutes added to the length of a movie for it to fit, e.g. for a break
+    pub buffer: u64,
+}
+
+impl Default for BudgetSettings {
+    fn default() -> Self {
+        Self { buffer: 10 }
+    }
+}
+
+/// Time left to watch something, up to a deadline in local time.
+#[derive(Clone, Copy)]
+pub struct TimeBudget {
+    deadline: OffsetDateTime,
+}
+
+impl TimeBudget {
+    /// Reads the local time offset. It can only be determined while the
+    /// program has a single thread, so this must be called first thing.
+    pub fn init_local_offset() {
+        LOCAL_OFFSET.get_or_init(|| UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC));
+    }
+
+    /// Current local time, which the other functions are given so that a
+    /// whole list is checked against the same instant.
+    pub fn now() -> OffsetDateTime {
+        let offset = LOCAL_OFFSET.get().copied().unwrap_or(UtcOffset::UTC);
+        OffsetDateTime::now_utc().to_offset(offset)
+    }
+
+    /// Parses a deadline such as `23:30`, taken as tomorrow if that time of
+    /// the day has already passed at `now`.
+    pub fn parse(until: &str, now: OffsetDateTime) -> Result<Self, Box<dyn Error>> {
+        let (hour, minute) = until
+            .trim()
+            .split_once(':')
+            .ok_or("Expected a time such as 23:30")?;
+        let time = Time::from_hms(hour.parse()?, minute.parse()?, 0)?;
+
+        let mut deadline = now.replace_time(time);
+        if deadline <= now {
+            deadline += Duration::DAY;
+        }
+        Ok(Self { deadline })
+    }
+
+    /// Whether something lasting `seconds`, plus the configured buffer,
+    /// ends before the deadline if started at `now`.
+    pub fn fits(&self, seconds: u64, settings: &BudgetSettings, now: OffsetDateTime) -> bool {
+        Self::end(seconds, settings, now).is_some_and(|end| end <= self.deadline)
+    }
+
+    /// Local time at which something lasting `seconds`, plus the configured
+    /// buffer, ends if started at `now`.
+    fn end(seconds: u64, settings: &BudgetSettings, now: OffsetDateTime) -> Option<OffsetDateTime> {
+        let needed = seconds.saturating_add(settings.buffer.saturating_mul(60));
+        let needed = Duration::try_from(std::time::Duration::from_secs(needed)).ok()?;
+        now.checked_add(needed)
+    }
+
+    /// Seconds left from `now` until the deadline.
+    pub fn remaining(&self, now: OffsetDateTime) -> u64 {
+        u64::try_from((self.deadline - now).whole_seconds()).unwrap_or(0)
+    }
+
+    pub fn pretty_deadline(&self) -> String {
+        pretty_time(self.deadline)
+    }
+}
+
+/// Local time at which something lasting `seconds`, plus the configured
+/// buffer, ends if started at `now`, e.g. `22:47`. The same end `fits`
+/// checks against the deadline.
+pub fn pretty_end(seconds: u64, settings: &BudgetSettings, now: OffsetDateTime) -> String {
+    TimeBudget::end(seconds, settings, now).map_or_else(|| "-".to_owned(), pretty_time)
+}
+
+fn pretty_time(time: OffsetDateTime) -> String {
+    format!("{:02}:{:02}", time.hour(), time.minute())
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{Date, Month};
+
+    use super::*;
+
+    /// 2026-10-18 at `hour`:`minute`, in UTC.
+    fn at(hour: u8, minute: u8) -> OffsetDateTime {
+        Date::from_calendar_date(2026, Month::October, 18)
+            .unwrap()
+            .with_hms(hour, minute, 0)
+            .unwrap()
+            .assume_utc()
+    }
+
+    #[test]
+    fn parse_rolls_over_to_tomorrow() {
+        let now = at(20, 0);
+
+        let budget = TimeBudget::parse("23:30", now).unwrap();
+        assert_eq!(budget.deadline, at(23, 30));
+        assert_eq!(budget.remaining(now), 3 * 3600 + 30 * 60);
+
+        for until in ["19:00", "20:00", " 0:15 "] {
+            let budget = TimeBudget::parse(until, now).unwrap();
+            assert_eq!(
+                budget.deadline.date(),
+                now.date().next_day().unwrap(),
+                "{until}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_rejects_invalid_times() {
+        for until in ["24:00", "7", "ab:cd", "12:60", ""] {
+            assert!(TimeBudget::parse(until, at(20, 0)).is_err(), "{until}");
+        }
+    }
+
+    #[test]
+    fn fits_counts_the_buffer() {
+        let now = at(20, 0);
+        let budget = TimeBudget::parse("22:00", now).unwrap();
+        let settings = BudgetSettings { buffer: 10 };
+
+        assert!(budget.fits(110 * 60, &settings, now));
+        assert!(!budget.fits(110 * 60 + 1, &settings, now));
+        assert!(budget.fits(120 * 60, &BudgetSettings { buffer: 0 }, now));
+        assert!(!budget.fits(u64::MAX, &settings, now));
+    }
+
+    #[test]
+    fn pretty_end_adds_the_buffer() {
+        let settings = BudgetSettings { buffer: 10 };
+
+        assert_eq!(pretty_end(90 * 60, &settings, at(20, 0)), "21:40");
+        assert_eq!(pretty_end(3 * 3600, &settings, at(23, 5)), "02:15");
+        assert_eq!(pretty_end(u64::MAX, &settings, at(20, 0)), "-");
+    }
+}
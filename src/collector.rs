@@ -1,33 +1,137 @@
 use ffmpeg_next::log::Level::Quiet;
 use std::{
+    collections::{BTreeMap, HashMap},
     error::Error,
     hash::{DefaultHasher, Hash, Hasher},
     path::Path,
 };
 use walkdir::WalkDir;
 
-use crate::movie::Movie;
+use crate::{
+    disc::Disc,
+    duplicates::Fingerprints,
+    movie::{Movie, MovieLength},
+    part::Part,
+    series::{Episode, Series},
+    title::Title,
+    transcoder::Transcoder,
+};
 
 pub struct Collector;
 
 impl Collector {
-    pub fn collect(path: &Path) -> (Vec<Movie>, u64) {
-        Self::ffmpeg_init().expect("Failed to initialize ffmpeg");
-
+    pub fn collect(path: &Path) -> (Vec<Movie>, Vec<Series>, u64) {
         let mut hash = DefaultHasher::new();
 
-        let mut movies: Vec<_> = WalkDir::new(path)
-            .into_iter()
-            .filter_map(|e| e.ok().and_then(|e| Movie::try_from(e.path()).ok()))
-            .collect();
+        let mut movies = Vec::new();
+        let mut episodes = Vec::new();
+        let mut parts: BTreeMap<_, Vec<Part>> = BTreeMap::new();
+
+        let mut entries = WalkDir::new(path).into_iter();
+        while let Some(entry) = entries.next() {
+            let Ok(entry) = entry else {
+                continue;
+            };
+
+            if let Ok(disc) = Disc::try_from(entry.path()) {
+                // The pieces of a disc structure are not movies on their own
+                if entry.file_type().is_dir() {
+                    entries.skip_current_dir();
+                }
+                movies.extend(Movie::try_from(disc).ok());
+                continue;
+            }
 
+            // Converted copies are linked to the movie they were made from
+            if Transcoder::is_output(entry.path()) {
+                continue;
+            }
+
+            if let Ok(episode) = Episode::try_from(entry.path()) {
+                episodes.push(episode);
+                continue;
+            }
+
+            match Part::try_from(entry.path()) {
+                Ok(part) => parts.entry(part.key()).or_default().push(part),
+                Err(_) => movies.extend(Movie::try_from(entry.path()).ok()),
+            }
+        }
+
+        movies.extend(parts.into_values().flat_map(Self::join_parts));
+
+        let mut movies = Self::group_versions(movies);
         movies.sort_by(|a, b| a.name().cmp(b.name()));
         movies.hash(&mut hash);
 
-        (movies, hash.finish())
+        let series = Series::group(episodes);
+        series.hash(&mut hash);
+
+        (movies, series, hash.finish())
+    }
+
+    /// Movie made of files named like its parts. A lone file that merely
+    /// looks like a part is a regular movie, and so are "Part 1" and "Part 2"
+    /// when each is long enough to be one, e.g. `Deathly Hallows Part 1`.
+    fn join_parts(group: Vec<Part>) -> Vec<Movie> {
+        let separate = group.len() == 1
+            || group.iter().all(|part| {
+                part.may_be_sequel() && MovieLength::try_from(part.path().as_path()).is_ok()
+            });
+        if separate {
+            return group
+                .iter()
+                .filter_map(|part| Movie::try_from(part.path().as_path()).ok())
+                .collect();
+        }
+        Movie::try_from(group).into_iter().collect()
+    }
+
+    /// Merges likely copies of the same movie: same parsed title and year, or
+    /// same content fingerprint. Fingerprints of unchanged files are reused
+    /// from the previous run.
+    fn group_versions(movies: Vec<Movie>) -> Vec<Movie> {
+        let mut groups: Vec<Vec<Movie>> = Vec::new();
+        let mut by_title: HashMap<Title, usize> = HashMap::new();
+        let mut by_fingerprint: HashMap<u64, usize> = HashMap::new();
+        let mut fingerprints = Fingerprints::load();
+
+        for movie in movies {
+            // Without a year, titles like "Dune" are too ambiguous to match on
+            let title = Some(Title::from(movie.name())).filter(|title| title.year().is_some());
+            let fingerprint = movie
+                .paths()
+                .next()
+                .filter(|_| !movie.is_disc())
+                .and_then(|path| fingerprints.get(path).ok());
+
+            let index = title
+                .as_ref()
+                .and_then(|title| by_title.get(title))
+                .or_else(|| fingerprint.and_then(|f| by_fingerprint.get(&f)))
+                .copied()
+                .unwrap_or_else(|| {
+                    groups.push(Vec::new());
+                    groups.len() - 1
+                });
+
+            groups[index].push(movie);
+            if let Some(title) = title {
+                by_title.entry(title).or_insert(index);
+            }
+            if let Some(fingerprint) = fingerprint {
+                by_fingerprint.entry(fingerprint).or_insert(index);
+            }
+        }
+
+        fingerprints.save().ok();
+        groups
+            .into_iter()
+            .filter_map(Movie::with_versions)
+            .collect()
     }
 
-    fn ffmpeg_init() -> Result<(), Box<dyn Error>> {
+    pub fn ffmpeg_init() -> Result<(), Box<dyn Error>> {
         ffmpeg_next::init()?;
         ffmpeg_next::util::log::set_level(Quiet);
         Ok(())
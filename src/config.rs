@@ -0,0 +1,97 @@
+use std::{env, error::Error, fs, io, path::PathBuf, sync::OnceLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    budget::BudgetSettings, graphics::Protocol, player::PlayerSettings, surprise::SurpriseSettings,
+    table::TableSettings, transcoder::Profile,
+};
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// User settings read from `config.json` in the config directory. Missing
+/// fields take their default value.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Seconds into a movie at which its thumbnail frame is taken
+    pub thumbnail_offset: u64,
+    /// Seconds at the end of a movie searched for the start of the credits
+    pub credits_window: u64,
+    /// Number of frames in a contact sheet
+    pub contact_sheet_frames: usize,
+    /// Number of frames per row of a contact sheet
+    pub contact_sheet_columns: usize,
+    /// How posters are drawn: `auto`, `kitty`, `iterm2`, `sixel`, `blocks`
+    /// or `off`
+    pub graphics: Protocol,
+    /// Target of movie conversions
+    pub transcode: Profile,
+    /// How movies are played
+    pub player: PlayerSettings,
+    /// Default constraints of random picks
+    pub surprise: SurpriseSettings,
+    /// How movies are fitted in a time budget
+    pub time_budget: BudgetSettings,
+    /// Columns of the list when it is shown as a table
+    pub table: TableSettings,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            thumbnail_offset: 600,
+            credits_window: 900,
+            contact_sheet_frames: 12,
+            contact_sheet_columns: 4,
+            graphics: Protocol::Auto,
+            transcode: Profile::default(),
+            player: PlayerSettings::default(),
+            surprise: SurpriseSettings::default(),
+            time_budget: BudgetSettings::default(),
+            table: TableSettings::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Reads the config file, if there is one. Must be called before `get`
+    /// for the file to be taken into account.
+    pub fn init() -> Result<(), Box<dyn Error>> {
+        let config = match Self::config_dir().map(|dir| fs::read_to_string(dir.join("config.json")))
+        {
+            Some(Ok(json)) => serde_json::from_str(&json)?,
+            Some(Err(e)) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+            _ => Self::default(),
+        };
+        CONFIG.set(config).ok();
+        Ok(())
+    }
+
+    pub fn get() -> &'static Self {
+        CONFIG.get_or_init(Self::default)
+    }
+
+    /// `$XDG_CONFIG_HOME/cineteca`, or `~/.config/cineteca`.
+    pub fn config_dir() -> Option<PathBuf> {
+        Self::xdg_dir("XDG_CONFIG_HOME", ".config")
+    }
+
+    /// `$XDG_CACHE_HOME/cineteca`, or `~/.cache/cineteca`.
+    pub fn cache_dir() -> Option<PathBuf> {
+        Self::xdg_dir("XDG_CACHE_HOME", ".cache")
+    }
+
+    fn xdg_dir(var: &str, fallback: &str) -> Option<PathBuf> {
+        Self::xdg_base(var, fallback).map(|dir| dir.join("cineteca"))
+    }
+
+    /// Base directory set by an XDG variable, or `fallback` in the home
+    /// directory, shared with other programs.
+    pub fn xdg_base(var: &str, fallback: &str) -> Option<PathBuf> {
+        env::var_os(var)
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(fallback)))
+    }
+}
@@ -0,0 +1,155 @@
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    artwork::{Artwork, Image},
+    config::Config,
+    movie::{self, Movie, MovieLength},
+};
+
+const TILE_WIDTH: usize = 320;
+const GAP: usize = 4;
+/// Size in pixels of each dot of the timestamp font.
+const FONT_SCALE: usize = 2;
+const BACKGROUND: [u8; 3] = [16, 16, 16];
+const TEXT: [u8; 3] = [255, 255, 255];
+
+/// 5x7 glyphs for the characters of a timestamp, one row per byte.
+const GLYPHS: [(char, [u8; 7]); 11] = [
+    ('0', [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E]),
+    ('1', [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E]),
+    ('2', [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F]),
+    ('3', [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E]),
+    ('4', [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02]),
+    ('5', [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E]),
+    ('6', [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E]),
+    ('7', [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08]),
+    ('8', [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E]),
+    ('9', [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C]),
+    (':', [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00]),
+];
+
+/// Grid of frames taken at regular intervals through a movie, each labelled
+/// with its position.
+pub struct ContactSheet;
+
+impl ContactSheet {
+    /// Cached contact sheet of the movie, if it is up to date.
+    pub fn find(movie: &Movie) -> Option<PathBuf> {
+        Self::path(movie).filter(|path| Artwork::is_fresh(path, movie))
+    }
+
+    /// Samples the configured number of frames and writes the sheet to the
+    /// cache.
+    pub fn generate(movie: &Movie) -> Result<PathBuf, Box<dyn Error>> {
+        if movie.is_disc() {
+            return Err("Contact sheets are not available for discs".into());
+        }
+        let path = Self::path(movie).ok_or("No cache directory")?;
+        let config = Config::get();
+        let count = config.contact_sheet_frames.max(1);
+        let columns = config.contact_sheet_columns.clamp(1, count);
+
+        let parts: Vec<(&Path, u64)> = movie
+            .paths()
+            .map(|path| (path.as_path(), MovieLength::probe(path).unwrap_or(0)))
+            .collect();
+        let length: u64 = parts.iter().map(|(_, length)| length).sum();
+
+        let mut sheet: Option<Image> = None;
+        let mut tile_height = 0;
+        for i in 0..count {
+            // Centered in each of `count` equal slices of the movie
+            let position = length * (2 * i as u64 + 1) / (2 * count as u64);
+            let (file, offset) = Self::locate(&parts, position).ok_or("Movie has no files")?;
+            let frame = Artwork::grab_frame(file, offset)?;
+
+            let sheet = sheet.get_or_insert_with(|| {
+                tile_height =
+                    frame.height() as usize * TILE_WIDTH / (frame.width() as usize).max(1);
+                let rows = count.div_ceil(columns);
+                let mut sheet = Image::new(
+                    columns * (TILE_WIDTH + GAP) + GAP,
+                    rows * (tile_height + GAP) + GAP,
+                );
+                sheet.fill(0, 0, sheet.width(), sheet.height(), BACKGROUND);
+                sheet
+            });
+
+            let tile = Artwork::scale(
+                &frame,
+                u32::try_from(TILE_WIDTH)?,
+                u32::try_from(tile_height.max(1))?,
+            )?;
+            let x = GAP + i % columns * (TILE_WIDTH + GAP);
+            let y = GAP + i / columns * (tile_height + GAP);
+            sheet.draw_frame(&tile, x, y);
+            Self::draw_timestamp(sheet, &movie::pretty_position(position), x, y + tile_height);
+        }
+
+        let sheet = sheet.ok_or("No frames to sample")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, sheet.to_png()?)?;
+
+        Ok(path)
+    }
+
+    fn path(movie: &Movie) -> Option<PathBuf> {
+        Config::cache_dir().map(|dir| {
+            dir.join("contact_sheets")
+                .join(format!("{}.png", movie.id()))
+        })
+    }
+
+    /// File and offset within it of a position in a movie split in parts.
+    fn locate<'a>(parts: &[(&'a Path, u64)], position: u64) -> Option<(&'a Path, u64)> {
+        let mut remaining = position;
+        for &(path, length) in parts {
+            if remaining < length {
+                return Some((path, remaining));
+            }
+            remaining -= length;
+        }
+        parts
+            .last()
+            .map(|&(path, length)| (path, length.saturating_sub(1)))
+    }
+
+    /// Draws the text on a dark box, its bottom left corner at `x`, `bottom`.
+    fn draw_timestamp(sheet: &mut Image, text: &str, x: usize, bottom: usize) {
+        let glyph_width = 6 * FONT_SCALE;
+        let height = 9 * FONT_SCALE;
+        let top = bottom.saturating_sub(height);
+
+        sheet.fill(
+            x,
+            top,
+            text.len() * glyph_width + FONT_SCALE,
+            height,
+            [0, 0, 0],
+        );
+
+        for (i, c) in text.chars().enumerate() {
+            let Some((_, rows)) = GLYPHS.iter().find(|(glyph, _)| *glyph == c) else {
+                continue;
+            };
+            let left = x + FONT_SCALE + i * glyph_width;
+            for (row, bits) in rows.iter().enumerate() {
+                for column in (0..5).filter(|column| bits & (0x10 >> column) != 0) {
+                    sheet.fill(
+                        left + column * FONT_SCALE,
+                        top + FONT_SCALE + row * FONT_SCALE,
+                        FONT_SCALE,
+                        FONT_SCALE,
+                        TEXT,
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use std::{error::Error, path::Path};
+
+use ffmpeg_next::{
+    Packet, codec,
+    format::{self, Pixel},
+    frame, media,
+    software::scaling,
+};
+
+use crate::{
+    config::Config,
+    movie::{Movie, MovieLength},
+};
+
+/// Frames analysed per second of the searched window.
+const SAMPLES_PER_SECOND: u64 = 2;
+/// Size frames are shrunk to before measuring them.
+const SAMPLE_WIDTH: u32 = 64;
+const SAMPLE_HEIGHT: u32 = 36;
+/// Luma below which a pixel counts as dark.
+const DARK_LUMA: u8 = 40;
+/// Percentage of dark pixels of a frame of credits, light text on black.
+/// High enough for dark scenes with black bars not to count.
+const CREDITS_DARKNESS: u8 = 90;
+/// Percentage of the frames from the start of the credits to the end of the
+/// movie that have to look like credits.
+const CREDITS_SHARE: usize = 90;
+/// Shortest run of credits, in seconds, so that a dark last scene is not
+/// taken for them.
+const MIN_CREDITS: u64 = 60;
+
+/// A frame measured while looking for the credits.
+struct Sample {
+    /// Milliseconds into the file
+    position: u64,
+    /// Percentage of dark pixels
+    darkness: u8,
+}
+
+pub struct Credits;
+
+impl Credits {
+    /// Estimates where the end credits begin, in seconds into the movie, by
+    /// looking for the mostly black frames they are made of near its end.
+    /// Movies whose credits could not be told apart start them at the very
+    /// end.
+    pub fn detect(movie: &Movie) -> Result<u64, Box<dyn Error>> {
+        if movie.is_disc() {
+            return Err("Credits cannot be detected on discs".into());
+        }
+        let path = movie.paths().last().ok_or("Movie has no files")?;
+        let length = movie.length();
+        let file_length = MovieLength::probe(path)?;
+        // Earlier parts of a movie split in several files
+        let offset = length.saturating_sub(file_length);
+
+        let window = Config::get().credits_window.min(file_length / 2);
+        let samples = Self::sample(path, file_length - window)?;
+
+        Ok(Self::find_start(&samples).map_or(length, |start| offset + start / 1000))
+    }
+
+    /// Measures the frames from `start` seconds to the end of the file.
+    fn sample(path: &Path, start: u64) -> Result<Vec<Sample>, Box<dyn Error>> {
+        let mut input = format::input(&path)?;
+
+        let (index, time_base, mut decoder) = {
+            let stream = input
+                .streams()
+                .best(media::Type::Video)
+                .ok_or("No video stream")?;
+            let decoder = codec::context::Context::from_parameters(stream.parameters())?
+                .decoder()
+                .video()?;
+            (stream.index(), stream.time_base(), decoder)
+        };
+
+        let position = i64::try_from(start)? * i64::from(ffmpeg_next::ffi::AV_TIME_BASE);
+        input.seek(position, ..position)?;
+
+        let mut samples = Vec::new();
+        let mut next_sample = start * 1000;
+        let mut context: Option<scaling::Context> = None;
+        let mut packet = Packet::empty();
+        let mut frame = frame::Video::empty();
+        let mut scaled = frame::Video::empty();
+
+        loop {
+            let eof = match packet.read(&mut input) {
+                Ok(()) if packet.stream() != index => continue,
+                Ok(()) => {
+                    decoder.send_packet(&packet)?;
+                    false
+                }
+                Err(ffmpeg_next::Error::Eof) => {
+                    decoder.send_eof()?;
+                    true
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            while decoder.receive_frame(&mut frame).is_ok() {
+                let Some(timestamp) = frame.timestamp() else {
+                    continue;
+                };
+                let position = u64::try_from(
+                    i128::from(timestamp) * 1000 * i128::from(time_base.numerator())
+                        / i128::from(time_base.denominator().max(1)),
+                )
+                .unwrap_or(0);
+                if position < next_sample {
+                    continue;
+                }
+                next_sample = position + 1000 / SAMPLES_PER_SECOND;
+
+                let context = match &mut context {
+                    Some(context) => context,
+                    None => context.insert(scaling::Context::get(
+                        frame.format(),
+                        frame.width(),
+                        frame.height(),
+                        Pixel::GRAY8,
+                        SAMPLE_WIDTH,
+                        SAMPLE_HEIGHT,
+                        scaling::Flags::AREA,
+                    )?),
+                };
+                context.run(&frame, &mut scaled)?;
+                samples.push(Sample {
+                    position,
+                    darkness: Self::darkness(&scaled),
+                });
+            }
+
+            if eof {
+                break;
+            }
+        }
+
+        Ok(samples)
+    }
+
+    /// Percentage of dark pixels of a grayscale frame.
+    fn darkness(frame: &frame::Video) -> u8 {
+        let width = frame.width() as usize;
+        let stride = frame.stride(0);
+        let data = frame.data(0);
+
+        let (dark, total) = (0..frame.height() as usize)
+            .filter_map(|row| data.get(row * stride..row * stride + width))
+            .flatten()
+            .fold((0, 0), |(dark, total), &luma| {
+                (dark + usize::from(luma < DARK_LUMA), total + 1)
+            });
+
+        u8::try_from(dark * 100 / usize::max(total, 1)).unwrap_or(100)
+    }
+
+    /// Earliest credits-like sample from which nearly every frame up to the
+    /// end looks like credits too, lasting long enough to be them.
+    fn find_start(samples: &[Sample]) -> Option<u64> {
+        let end = samples.last()?.position;
+        let mut credits = 0;
+        let mut start = None;
+
+        for (i, sample) in samples.iter().enumerate().rev() {
+            let is_dark = sample.darkness >= CREDITS_DARKNESS;
+            credits += usize::from(is_dark);
+            let total = samples.len() - i;
+            if is_dark
+                && credits * 100 >= total * CREDITS_SHARE
+                && end - sample.position >= MIN_CREDITS * 1000
+            {
+                start = Some(sample.position);
+            }
+        }
+
+        start
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BRIGHT: u8 = 20;
+    const DARK: u8 = 95;
+
+    /// Two samples per second over `seconds`, measured by `darkness` from
+    /// the sample number.
+    fn samples(seconds: u64, darkness: impl Fn(u64) -> u8) -> Vec<Sample> {
+        (0..seconds * SAMPLES_PER_SECOND)
+            .map(|i| Sample {
+                position: i * 1000 / SAMPLES_PER_SECOND,
+                darkness: darkness(i),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn credits_start_at_their_first_frame() {
+        let samples = samples(300, |i| if i < 400 { BRIGHT } else { DARK });
+        assert_eq!(Credits::find_start(&samples), Some(200_000));
+    }
+
+    #[test]
+    fn credits_may_have_a_few_bright_frames() {
+        let samples = samples(300, |i| if i < 400 || i % 20 == 0 { BRIGHT } else { DARK });
+        assert_eq!(Credits::find_start(&samples), Some(200_500));
+    }
+
+    #[test]
+    fn a_short_dark_last_scene_is_not_credits() {
+        let dark_from = (300 - MIN_CREDITS / 2) * SAMPLES_PER_SECOND;
+        let samples = samples(300, |i| if i < dark_from { BRIGHT } else { DARK });
+        assert_eq!(Credits::find_start(&samples), None);
+    }
+
+    #[test]
+    fn scattered_dark_frames_are_not_credits() {
+        let samples = samples(300, |i| if i % 4 == 0 { DARK } else { BRIGHT });
+        assert_eq!(Credits::find_start(&samples), None);
+    }
+
+    #[test]
+    fn movies_without_credits_have_no_start() {
+        assert_eq!(Credits::find_start(&samples(300, |_| BRIGHT)), None);
+        assert_eq!(Credits::find_start(&[]), None);
+    }
+}
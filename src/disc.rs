@@ -0,0 +1,195 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use ffmpeg_next::{Dictionary, format};
+use serde::{Deserialize, Serialize};
+
+use crate::movie::MovieLength;
+
+#[derive(Serialize, Deserialize, Eq, PartialEq, Clone, Copy, Hash)]
+pub enum DiscKind {
+    Dvd,
+    BluRay,
+}
+
+impl DiscKind {
+    /// URI scheme understood by disc-aware players such as VLC and mpv.
+    pub const fn scheme(self) -> &'static str {
+        match self {
+            Self::Dvd => "dvd",
+            Self::BluRay => "bluray",
+        }
+    }
+}
+
+/// A DVD or Blu-ray, either as a `VIDEO_TS`/`BDMV` folder structure or as an
+/// ISO image.
+pub struct Disc {
+    kind: DiscKind,
+    name: String,
+    root: PathBuf,
+    length: u64,
+}
+
+impl Disc {
+    pub const fn kind(&self) -> DiscKind {
+        self.kind
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The folder containing `VIDEO_TS`/`BDMV`, or the ISO image itself.
+    pub const fn root(&self) -> &PathBuf {
+        &self.root
+    }
+
+    /// Length of the main title in seconds.
+    pub const fn length(&self) -> u64 {
+        self.length
+    }
+
+    fn from_folder(kind: DiscKind, structure: &Path) -> Result<Self, Box<dyn Error>> {
+        let root = structure.parent().ok_or("Disc structure has no parent")?;
+        let name = root
+            .file_name()
+            .ok_or("Disc folder does not have a valid name")?
+            .to_string_lossy()
+            .into_owned();
+
+        let length = Self::probe_disc(kind, root).or_else(|_| match kind {
+            DiscKind::Dvd => Self::probe_title_sets(structure),
+            DiscKind::BluRay => Self::probe_streams(&structure.join("STREAM")),
+        })?;
+
+        Ok(Self {
+            kind,
+            name,
+            root: root.to_path_buf(),
+            length,
+        })
+    }
+
+    fn from_iso(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let name = path
+            .file_stem()
+            .ok_or("Path does not have a valid filename")?
+            .to_string_lossy()
+            .into_owned();
+
+        let (kind, length) = [DiscKind::BluRay, DiscKind::Dvd]
+            .into_iter()
+            .find_map(|kind| Some((kind, Self::probe_disc(kind, path).ok()?)))
+            .ok_or("ISO image is not a readable DVD or Blu-ray")?;
+
+        Ok(Self {
+            kind,
+            name,
+            root: path.to_path_buf(),
+            length,
+        })
+    }
+
+    /// Length of the main title as selected by ffmpeg's own disc support,
+    /// which is only available when built with libdvdnav/libbluray.
+    fn probe_disc(kind: DiscKind, root: &Path) -> Result<u64, Box<dyn Error>> {
+        let input = match kind {
+            DiscKind::BluRay => format::input(&format!("bluray:{}", root.display()))?,
+            DiscKind::Dvd => {
+                // SAFETY: the name is a NUL-terminated literal, and ffmpeg
+                // only reads it during the call. The result is null or points
+                // to one of ffmpeg's static demuxer descriptions, which live
+                // as long as the process and are never freed
+                let demuxer =
+                    unsafe { ffmpeg_next::ffi::av_find_input_format(c"dvdvideo".as_ptr()) };
+                if demuxer.is_null() {
+                    return Err("ffmpeg was built without DVD support".into());
+                }
+                // SAFETY: the pointer is non-null and static, as checked
+                // above. `Input` neither frees nor writes through it, so
+                // casting away the const is sound
+                let demuxer = unsafe { format::format::Input::wrap(demuxer.cast_mut()) };
+                let demuxer = format::Format::Input(demuxer);
+                format::open_with(root, &demuxer, Dictionary::new())?.input()
+            }
+        };
+
+        Ok(MovieLength::from_input(&input))
+    }
+
+    /// Length of the longest title set, summing its `VTS_XX_N.VOB` pieces.
+    fn probe_title_sets(video_ts: &Path) -> Result<u64, Box<dyn Error>> {
+        let mut title_sets: HashMap<String, u64> = HashMap::new();
+
+        for entry in fs::read_dir(video_ts)?.filter_map(Result::ok) {
+            let file_name = entry.file_name().to_string_lossy().to_ascii_uppercase();
+            let Some(title_set) = file_name
+                .strip_prefix("VTS_")
+                .and_then(|rest| rest.strip_suffix(".VOB"))
+                .and_then(|rest| rest.split_once('_'))
+                .filter(|(_, piece)| *piece != "0")
+                .map(|(title_set, _)| title_set.to_owned())
+            else {
+                continue;
+            };
+
+            // A damaged piece only shortens its title set instead of hiding
+            // the whole disc
+            let Ok(length) = MovieLength::probe(&entry.path()) else {
+                continue;
+            };
+            *title_sets.entry(title_set).or_default() += length;
+        }
+
+        title_sets
+            .into_values()
+            .max()
+            .ok_or_else(|| "DVD does not contain any title sets".into())
+    }
+
+    /// Length of the longest `.m2ts` stream of a Blu-ray.
+    fn probe_streams(stream: &Path) -> Result<u64, Box<dyn Error>> {
+        fs::read_dir(stream)?
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("m2ts"))
+            })
+            .filter_map(|path| MovieLength::probe(&path).ok())
+            .max()
+            .ok_or_else(|| "Blu-ray does not contain any streams".into())
+    }
+}
+
+impl TryFrom<&Path> for Disc {
+    type Error = Box<dyn Error>;
+
+    /// Accepts a `VIDEO_TS` or `BDMV` folder, or an `.iso` file.
+    fn try_from(path: &Path) -> Result<Self, Self::Error> {
+        let file_name = path
+            .file_name()
+            .ok_or("Path does not have a valid filename")?
+            .to_string_lossy();
+
+        if path.is_dir() {
+            match file_name.to_ascii_uppercase().as_str() {
+                "VIDEO_TS" => Self::from_folder(DiscKind::Dvd, path),
+                "BDMV" => Self::from_folder(DiscKind::BluRay, path),
+                _ => Err("Not a disc structure".into()),
+            }
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("iso"))
+        {
+            Self::from_iso(path)
+        } else {
+            Err("Not a disc image".into())
+        }
+    }
+}
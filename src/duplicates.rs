@@ -0,0 +1,263 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs::{self, File},
+    hash::{DefaultHasher, Hasher},
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    archive::Archive,
+    config::Config,
+    fnv::Fnv,
+    movie::{self, Movie},
+    series::{Episode, Series},
+};
+
+const SAMPLE_SIZE: u64 = 64 * 1024;
+const FINGERPRINTS_FILE: &str = "fingerprints.json";
+
+/// Cheap content fingerprint from the file size and its first and last
+/// bytes, good enough to tell apart files that are not copies of each other.
+pub fn fingerprint(path: &Path) -> io::Result<u64> {
+    let mut file = File::open(path)?;
+    let size = file.metadata()?.len();
+    let mut hasher = Fnv::default();
+    let mut buffer = Vec::new();
+
+    hasher.write(&size.to_le_bytes());
+
+    file.by_ref().take(SAMPLE_SIZE).read_to_end(&mut buffer)?;
+    file.seek(SeekFrom::Start(size.saturating_sub(SAMPLE_SIZE)))?;
+    file.take(SAMPLE_SIZE).read_to_end(&mut buffer)?;
+    hasher.write(&buffer);
+
+    Ok(hasher.finish())
+}
+
+/// A file as it was when its fingerprint was taken.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+struct Fingerprinted {
+    size: u64,
+    modified: SystemTime,
+    fingerprint: u64,
+}
+
+/// Fingerprints kept in the cache directory between runs, reused while the
+/// size and modification time of their file are unchanged.
+#[derive(Default)]
+pub struct Fingerprints {
+    saved: HashMap<PathBuf, Fingerprinted>,
+    /// Files looked up during this run, the only ones saved again
+    used: HashMap<PathBuf, Fingerprinted>,
+}
+
+impl Fingerprints {
+    pub fn load() -> Self {
+        let saved = Config::cache_dir()
+            .and_then(|dir| fs::read_to_string(dir.join(FINGERPRINTS_FILE)).ok())
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Self {
+            saved,
+            used: HashMap::new(),
+        }
+    }
+
+    /// Fingerprint of the file, taken again only when it changed.
+    pub fn get(&mut self, path: &Path) -> io::Result<u64> {
+        let metadata = path.metadata()?;
+        let (size, modified) = (metadata.len(), metadata.modified()?);
+        let cached = self
+            .saved
+            .get(path)
+            .filter(|cached| cached.size == size && cached.modified == modified);
+
+        let fingerprint = match cached {
+            Some(cached) => cached.fingerprint,
+            None => fingerprint(path)?,
+        };
+        self.used.insert(
+            path.to_path_buf(),
+            Fingerprinted {
+                size,
+                modified,
+                fingerprint,
+            },
+        );
+        Ok(fingerprint)
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let dir = Config::cache_dir().ok_or("No cache directory")?;
+        fs::create_dir_all(&dir)?;
+        fs::write(
+            dir.join(FINGERPRINTS_FILE),
+            serde_json::to_string(&self.used)?,
+        )?;
+        Ok(())
+    }
+}
+
+/// Hash of the whole file content.
+fn content_hash(path: &Path) -> io::Result<u64> {
+    let mut file = File::open(path)?;
+    let mut hasher = DefaultHasher::new();
+    let mut buffer = vec![0; 1024 * 1024];
+
+    loop {
+        match file.read(&mut buffer)? {
+            0 => return Ok(hasher.finish()),
+            n => hasher.write(&buffer[..n]),
+        }
+    }
+}
+
+/// Files in the library with exactly the same content.
+pub struct DuplicateReport {
+    groups: Vec<(u64, Vec<PathBuf>)>,
+}
+
+impl DuplicateReport {
+    pub fn scan(archive: &Archive) -> Self {
+        let movie_files = archive
+            .movies
+            .iter()
+            .flat_map(|movie| std::iter::once(movie).chain(movie.versions()))
+            .filter(|movie| !movie.is_disc())
+            .flat_map(Movie::paths);
+        let episode_files = archive
+            .series
+            .iter()
+            .flat_map(Series::episodes)
+            .map(Episode::path);
+
+        let mut by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+        for path in movie_files.map(PathBuf::as_path).chain(episode_files) {
+            if let Ok(metadata) = path.metadata() {
+                by_size
+                    .entry(metadata.len())
+                    .or_default()
+                    .push(path.to_path_buf());
+            }
+        }
+
+        // Only files of equal size and fingerprint are read in full
+        let mut groups: Vec<(u64, Vec<PathBuf>)> = by_size
+            .into_iter()
+            .filter(|(_, paths)| paths.len() > 1)
+            .flat_map(|(size, paths)| {
+                Self::split_by(paths, fingerprint)
+                    .into_iter()
+                    .flat_map(|paths| Self::split_by(paths, content_hash))
+                    .map(move |paths| (size, paths))
+            })
+            .collect();
+
+        groups.sort_by_key(|(size, paths)| std::cmp::Reverse(Self::wasted(*size, paths)));
+
+        Self { groups }
+    }
+
+    pub fn wasted_total(&self) -> u64 {
+        self.groups
+            .iter()
+            .map(|(size, paths)| Self::wasted(*size, paths))
+            .sum()
+    }
+
+    const fn wasted(size: u64, paths: &[PathBuf]) -> u64 {
+        size * (paths.len() as u64 - 1)
+    }
+
+    fn split_by(paths: Vec<PathBuf>, key: fn(&Path) -> io::Result<u64>) -> Vec<Vec<PathBuf>> {
+        let mut groups: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+        for path in paths {
+            if let Ok(key) = key(&path) {
+                groups.entry(key).or_default().push(path);
+            }
+        }
+
+        groups
+            .into_values()
+            .filter(|paths| paths.len() > 1)
+            .collect()
+    }
+}
+
+impl Display for DuplicateReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.groups.is_empty() {
+            return writeln!(f, "No byte-identical duplicates found");
+        }
+
+        for (size, paths) in &self.groups {
+            writeln!(
+                f,
+                "{} wasted ({} copies of {})",
+                movie::pretty_size(Self::wasted(*size, paths)),
+                paths.len(),
+                movie::pretty_size(*size)
+            )?;
+            for path in paths {
+                writeln!(f, "  {}", path.display())?;
+            }
+            writeln!(f)?;
+        }
+
+        writeln!(
+            f,
+            "Total wasted: {}",
+            movie::pretty_size(self.wasted_total())
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    #[test]
+    fn fingerprints_are_taken_again_when_the_file_changes() {
+        let path = env::temp_dir().join(format!("cineteca-test-{}-fingerprint", process::id()));
+        fs::write(&path, b"first version").unwrap();
+        let mut fingerprints = Fingerprints::default();
+
+        let first = fingerprints.get(&path).unwrap();
+        assert_eq!(first, fingerprint(&path).unwrap());
+        fingerprints.saved = fingerprints.used.clone();
+        assert_eq!(fingerprints.get(&path).unwrap(), first);
+
+        fs::write(&path, b"second, longer version").unwrap();
+        let second = fingerprints.get(&path).unwrap();
+        assert_ne!(second, first);
+        assert_eq!(second, fingerprint(&path).unwrap());
+        fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn cached_fingerprints_are_trusted_while_the_file_is_unchanged() {
+        let path = env::temp_dir().join(format!("cineteca-test-{}-cached", process::id()));
+        fs::write(&path, b"content").unwrap();
+        let metadata = path.metadata().unwrap();
+        let mut fingerprints = Fingerprints::default();
+        fingerprints.saved.insert(
+            path.clone(),
+            Fingerprinted {
+                size: metadata.len(),
+                modified: metadata.modified().unwrap(),
+                fingerprint: 42,
+            },
+        );
+
+        assert_eq!(fingerprints.get(&path).unwrap(), 42);
+        assert_eq!(fingerprints.used[&path].fingerprint, 42);
+        fs::remove_file(path).ok();
+    }
+}
@@ -0,0 +1,125 @@
+use std::iter;
+
+/// Lowercase letters without diacritics a character stands for, e.g. `É`
+/// becomes `e` and `ß` becomes `ss`.
+fn fold(c: char) -> impl Iterator<Item = char> {
+    let c = c.to_lowercase().next().unwrap_or(c);
+    let (first, second) = match c {
+        'ß' => ('s', Some('s')),
+        'æ' => ('a', Some('e')),
+        'œ' => ('o', Some('e')),
+        c => (strip(c), None),
+    };
+    iter::once(first).chain(second)
+}
+
+/// Lowercase letter without its diacritics.
+const fn strip(c: char) -> char {
+    match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => 'a',
+        'ç' | 'ć' | 'č' => 'c',
+        'ď' | 'đ' => 'd',
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ė' | 'ę' | 'ě' => 'e',
+        'ğ' => 'g',
+        'ì' | 'í' | 'î' | 'ï' | 'ī' | 'į' | 'ı' => 'i',
+        'ł' | 'ľ' => 'l',
+        'ñ' | 'ń' | 'ň' => 'n',
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ő' => 'o',
+        'ř' => 'r',
+        'ś' | 'š' | 'ş' => 's',
+        'ť' | 'ţ' => 't',
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ů' | 'ű' => 'u',
+        'ý' | 'ÿ' => 'y',
+        'ź' | 'ż' | 'ž' => 'z',
+        c => c,
+    }
+}
+
+/// Finds the characters of `query` in `text`, in order but not necessarily
+/// next to each other, ignoring case, diacritics and spaces in the query.
+/// Returns the indices of the matched characters of `text`, preferring
+/// matches at the start of words.
+pub fn find(query: &str, text: &str) -> Option<Vec<usize>> {
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(fold)
+        .collect();
+    // Folded characters, with the index of the character of `text` each
+    // comes from
+    let text: Vec<(usize, char)> = text
+        .chars()
+        .enumerate()
+        .flat_map(|(i, c)| fold(c).map(move |folded| (i, folded)))
+        .collect();
+    let is_word_start =
+        |i: usize| i == 0 || (text[i - 1].0 != text[i].0 && !text[i - 1].1.is_alphanumeric());
+
+    let mut indices: Vec<usize> = Vec::with_capacity(query.len());
+    let mut from = 0;
+    for (n, &wanted) in query.iter().enumerate() {
+        let mut candidates = (from..text.len()).filter(|&i| text[i].1 == wanted);
+        let first = candidates.next()?;
+        // Jumping ahead to a word start is only done when the rest of the
+        // query can still be found after it
+        let index = candidates
+            .find(|&i| {
+                is_word_start(i) && !is_word_start(first) && fits(&query[n + 1..], &text[i + 1..])
+            })
+            .unwrap_or(first);
+        // Both letters of a character such as `ß` may be matched
+        if indices.last() != Some(&text[index].0) {
+            indices.push(text[index].0);
+        }
+        from = index + 1;
+    }
+
+    Some(indices)
+}
+
+/// Whether `query` is a subsequence of the folded characters of `text`.
+fn fits(query: &[char], text: &[(usize, char)]) -> bool {
+    let mut text = text.iter();
+    query.iter().all(|wanted| text.any(|(_, c)| c == wanted))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_matches_as_a_subsequence() {
+        assert_eq!(find("ali", "Alien"), Some(vec![0, 1, 2]));
+        assert_eq!(find("aln", "Alien"), Some(vec![0, 1, 4]));
+        assert_eq!(
+            find("bld rnr", "Blade Runner"),
+            Some(vec![0, 1, 3, 6, 8, 11])
+        );
+        assert_eq!(find("nla", "Alien"), None);
+        assert_eq!(find("", "Alien"), Some(vec![]));
+    }
+
+    #[test]
+    fn matches_prefer_the_start_of_words() {
+        // The `r` of `Bar` is skipped for the one starting `Runner`
+        assert_eq!(find("br", "Bar Runner"), Some(vec![0, 4]));
+        // Unless the rest of the query is not found after the word start
+        assert_eq!(find("brs", "Bars Runner"), Some(vec![0, 2, 3]));
+    }
+
+    #[test]
+    fn case_and_diacritics_are_ignored() {
+        assert_eq!(find("AMELIE", "Amélie"), Some(vec![0, 1, 2, 3, 4, 5]));
+        assert_eq!(find("amélie", "AMELIE"), Some(vec![0, 1, 2, 3, 4, 5]));
+        assert_eq!(find("nino", "Niño"), Some(vec![0, 1, 2, 3]));
+    }
+
+    #[test]
+    fn ligatures_and_sharp_s_match_their_letters() {
+        assert_eq!(find("strasse", "Straße"), Some(vec![0, 1, 2, 3, 4, 5]));
+        assert_eq!(find("straße", "Strasse"), Some(vec![0, 1, 2, 3, 4, 5, 6]));
+        assert_eq!(find("coeur", "Cœur"), Some(vec![0, 1, 2, 3]));
+        assert_eq!(find("aeon", "Æon Flux"), Some(vec![0, 1, 2]));
+        assert_eq!(find("flux", "Æon Flux"), Some(vec![4, 5, 6, 7]));
+    }
+}
@@ -0,0 +1,174 @@
+use std::{env, fmt::Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{artwork::Image, base64};
+
+const KITTY_CHUNK_SIZE: usize = 4096;
+
+/// How images are drawn in the terminal.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    /// Guess from the environment
+    #[default]
+    Auto,
+    Kitty,
+    Iterm2,
+    Sixel,
+    /// Colored half-block characters, drawn as regular text
+    Blocks,
+    Off,
+}
+
+impl Protocol {
+    /// Resolves `Auto` to the protocol the terminal is known to support.
+    pub fn resolve(self) -> Self {
+        if self != Self::Auto {
+            return self;
+        }
+
+        let var = |name| env::var(name).unwrap_or_default();
+        let term = var("TERM");
+        let program = var("TERM_PROGRAM");
+
+        if env::var_os("KITTY_WINDOW_ID").is_some()
+            || term == "xterm-kitty"
+            || term == "xterm-ghostty"
+            || program == "ghostty"
+        {
+            Self::Kitty
+        } else if program == "iTerm.app" || program == "WezTerm" {
+            Self::Iterm2
+        } else if term.starts_with("foot") || term.starts_with("mlterm") || term == "yaft-256color"
+        {
+            Self::Sixel
+        } else {
+            Self::Blocks
+        }
+    }
+
+    /// Whether images are sent to the terminal as pixels, outside of the
+    /// cells drawn by cursive.
+    pub const fn is_pixel_based(self) -> bool {
+        matches!(self, Self::Kitty | Self::Iterm2 | Self::Sixel)
+    }
+
+    /// Escape sequence drawing the image at the cursor position, over an
+    /// area of `cols` by `rows` cells. `id` tells apart the images shown at
+    /// the same time, so that each can be replaced or removed.
+    pub fn encode(self, image: &Image, cols: usize, rows: usize, id: u32) -> String {
+        match self {
+            Self::Kitty => Self::kitty(image, cols, rows, id),
+            Self::Iterm2 => image
+                .to_png()
+                .map(|png| {
+                    format!(
+                        "\x1b]1337;File=inline=1;size={};width={cols};height={rows};preserveAspectRatio=0:{}\x07",
+                        png.len(),
+                        base64::encode(&png)
+                    )
+                })
+                .unwrap_or_default(),
+            Self::Sixel => Self::sixel(image),
+            Self::Auto | Self::Blocks | Self::Off => String::new(),
+        }
+    }
+
+    /// Escape sequence removing the image drawn by `encode`, for protocols
+    /// whose images are not simply overwritten by text.
+    pub fn clear(self, id: u32) -> Option<String> {
+        (self == Self::Kitty).then(|| format!("\x1b_Ga=d,d=I,i={id},q=2\x1b\\"))
+    }
+
+    fn kitty(image: &Image, cols: usize, rows: usize, id: u32) -> String {
+        let data = base64::encode(image.pixels());
+        let chunks: Vec<&[u8]> = data.as_bytes().chunks(KITTY_CHUNK_SIZE).collect();
+        let mut sequence = String::new();
+
+        for (i, chunk) in chunks.iter().enumerate() {
+            let more = u8::from(i + 1 < chunks.len());
+            let chunk = String::from_utf8_lossy(chunk);
+            if i == 0 {
+                write!(
+                    sequence,
+                    "\x1b_Ga=T,f=24,s={},v={},c={cols},r={rows},i={id},C=1,q=2,m={more};{chunk}\x1b\\",
+                    image.width(),
+                    image.height()
+                )
+            } else {
+                write!(sequence, "\x1b_Gm={more};{chunk}\x1b\\")
+            }
+            .ok();
+        }
+
+        sequence
+    }
+
+    /// Sixel data using a 6x6x6 color cube, one band of six rows at a time.
+    fn sixel(image: &Image) -> String {
+        let color = |x: usize, y: usize| {
+            let [r, g, b] = image.pixel(x, y).map(|c| (usize::from(c) * 5 + 127) / 255);
+            r * 36 + g * 6 + b
+        };
+
+        let mut sequence = format!("\x1bPq\"1;1;{};{}", image.width(), image.height());
+        for index in 0..216 {
+            let [r, g, b] = [index / 36, index / 6 % 6, index % 6].map(|c| c * 20);
+            write!(sequence, "#{index};2;{r};{g};{b}").ok();
+        }
+
+        for top in (0..image.height()).step_by(6) {
+            let rows = top..(top + 6).min(image.height());
+            let mut used = [false; 216];
+            for y in rows.clone() {
+                for x in 0..image.width() {
+                    used[color(x, y)] = true;
+                }
+            }
+
+            for index in (0..216).filter(|&index| used[index]) {
+                write!(sequence, "#{index}").ok();
+                let sixels = (0..image.width()).map(|x| {
+                    let bits = rows
+                        .clone()
+                        .filter(|&y| color(x, y) == index)
+                        .fold(0, |bits, y| bits | 1 << (y - top));
+                    char::from(63 + bits)
+                });
+                push_run_length(&mut sequence, sixels);
+                sequence.push('$');
+            }
+            sequence.push('-');
+        }
+
+        sequence + "\x1b\\"
+    }
+}
+
+/// Appends the sixels, compressing repeated ones as `!<count><sixel>`.
+fn push_run_length(sequence: &mut String, sixels: impl Iterator<Item = char>) {
+    let mut run: Option<(char, usize)> = None;
+    let flush = |sequence: &mut String, (sixel, count): (char, usize)| {
+        if count > 3 {
+            write!(sequence, "!{count}{sixel}").ok();
+        } else {
+            sequence.extend(std::iter::repeat_n(sixel, count));
+        }
+    };
+
+    for sixel in sixels {
+        match run {
+            Some((current, count)) if current == sixel => run = Some((current, count + 1)),
+            _ => {
+                if let Some(previous) = run {
+                    flush(sequence, previous);
+                }
+                run = Some((sixel, 1));
+            }
+        }
+    }
+    if let Some(previous) = run {
+        flush(sequence, previous);
+    }
+}
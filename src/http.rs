@@ -0,0 +1,201 @@
+use std::{
+    error::Error,
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+use serde_json::Value;
+
+use crate::base64;
+
+/// How long a request may take before the remote player is given up on.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Minimal HTTP/1.0 client for the JSON APIs of remote players.
+pub struct Http<'a> {
+    host: &'a str,
+    port: u16,
+    /// Basic authentication, as `user:password`
+    credentials: Option<String>,
+}
+
+impl<'a> Http<'a> {
+    pub fn new(host: &'a str, port: u16, username: &str, password: &str) -> Self {
+        let credentials = (!username.is_empty() || !password.is_empty())
+            .then(|| format!("{username}:{password}"));
+        Self {
+            host,
+            port,
+            credentials,
+        }
+    }
+
+    pub fn get(&self, target: &str) -> Result<Value, Box<dyn Error>> {
+        self.request("GET", target, None)
+    }
+
+    pub fn post(&self, target: &str, body: &Value) -> Result<Value, Box<dyn Error>> {
+        self.request("POST", target, Some(body))
+    }
+
+    fn request(
+        &self,
+        method: &str,
+        target: &str,
+        body: Option<&Value>,
+    ) -> Result<Value, Box<dyn Error>> {
+        let address = (self.host, self.port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| format!("Could not resolve {}", self.host))?;
+        let mut stream = TcpStream::connect_timeout(&address, TIMEOUT)
+            .map_err(|e| format!("Could not connect to {}:{}: {e}", self.host, self.port))?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+
+        let body = body.map(Value::to_string).unwrap_or_default();
+        let mut request = format!(
+            "{method} {target} HTTP/1.0\r\nHost: {}:{}\r\nAccept: application/json\r\n",
+            self.host, self.port
+        );
+        if let Some(credentials) = &self.credentials {
+            request.push_str("Authorization: Basic ");
+            request.push_str(&base64::encode(credentials.as_bytes()));
+            request.push_str("\r\n");
+        }
+        if method == "POST" {
+            request.push_str("Content-Type: application/json\r\n");
+            request.push_str("Content-Length: ");
+            request.push_str(&body.len().to_string());
+            request.push_str("\r\n");
+        }
+        request += "\r\n";
+        stream.write_all(request.as_bytes())?;
+        stream.write_all(body.as_bytes())?;
+
+        // HTTP/1.0 servers close the connection once the response is sent
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response)?;
+        let response = String::from_utf8_lossy(&response);
+        let (head, body) = response
+            .split_once("\r\n\r\n")
+            .ok_or("Malformed HTTP response")?;
+
+        let status = head
+            .split_whitespace()
+            .nth(1)
+            .and_then(|status| status.parse::<u16>().ok())
+            .ok_or("Malformed HTTP status line")?;
+        if !(200..300).contains(&status) {
+            return Err(format!("{} answered with HTTP status {status}", self.host).into());
+        }
+
+        Ok(serde_json::from_str(body)?)
+    }
+}
+
+/// Escapes everything but unreserved characters for use in a query string.
+pub fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                char::from(byte).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+/// Local HTTP server standing in for a remote player in tests.
+#[cfg(test)]
+pub mod mock {
+    use std::{
+        io::{Read, Write},
+        net::{TcpListener, TcpStream},
+        thread::{self, JoinHandle},
+    };
+
+    /// Answers one connection per response, given as an HTTP status and a
+    /// body, in order. Returns the port listened on and the requests
+    /// received, once every response was sent.
+    pub fn serve(responses: Vec<(u16, String)>) -> (u16, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            responses
+                .into_iter()
+                .map(|(status, body)| {
+                    let (mut stream, _) = listener.accept().unwrap();
+                    let request = read_request(&mut stream);
+                    write!(stream, "HTTP/1.0 {status} Status\r\n\r\n{body}").unwrap();
+                    request
+                })
+                .collect()
+        });
+        (port, server)
+    }
+
+    fn read_request(stream: &mut TcpStream) -> String {
+        let mut request = Vec::new();
+        let mut buffer = [0; 1024];
+        loop {
+            let read = stream.read(&mut buffer).unwrap();
+            request.extend_from_slice(&buffer[..read]);
+            let text = String::from_utf8_lossy(&request).into_owned();
+            let Some((head, body)) = text.split_once("\r\n\r\n") else {
+                continue;
+            };
+            let length = head
+                .lines()
+                .find_map(|line| line.strip_prefix("Content-Length: "))
+                .map_or(0, |length| length.parse().unwrap());
+            if read == 0 || body.len() >= length {
+                return text;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn percent_encode_escapes_reserved_characters() {
+        assert_eq!(percent_encode("Movie-1_a.b~"), "Movie-1_a.b~");
+        assert_eq!(
+            percent_encode("/mnt/a b&c=ä"),
+            "%2Fmnt%2Fa%20b%26c%3D%C3%A4"
+        );
+    }
+
+    #[test]
+    fn post_sends_credentials_and_parses_the_answer() {
+        let (port, server) = mock::serve(vec![(200, json!({ "ok": true }).to_string())]);
+        let http = Http::new("127.0.0.1", port, "kodi", "secret");
+
+        let response = http.post("/jsonrpc", &json!({ "a": 1 })).unwrap();
+
+        assert_eq!(response, json!({ "ok": true }));
+        let request = &server.join().unwrap()[0];
+        assert!(request.starts_with("POST /jsonrpc HTTP/1.0\r\n"));
+        // base64 of "kodi:secret"
+        assert!(request.contains("Authorization: Basic a29kaTpzZWNyZXQ=\r\n"));
+        assert!(request.ends_with("\r\n\r\n{\"a\":1}"));
+    }
+
+    #[test]
+    fn get_fails_on_error_status() {
+        let (port, server) = mock::serve(vec![(401, String::new())]);
+        let http = Http::new("127.0.0.1", port, "", "");
+
+        let error = http.get("/requests/status.json").unwrap_err();
+
+        assert!(error.to_string().contains("HTTP status 401"));
+        assert!(!server.join().unwrap()[0].contains("Authorization"));
+    }
+}
@@ -0,0 +1,274 @@
+use std::{
+    error::Error,
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde_json::{Value, json};
+
+use crate::{
+    http::Http,
+    player::{PlayRequest, Playback, PlayerBackend, Playing, RemoteSettings},
+};
+
+/// Time between two queries of the playback position.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// How long Kodi is given to start playing.
+const START_TIMEOUT: Duration = Duration::from_secs(30);
+/// Queries in a row that may fail, e.g. while the network drops for a moment,
+/// before Kodi is given up on.
+const MAX_FAILED_POLLS: u32 = 5;
+/// Percentage past which a stopped playback counts as played to the end.
+const FINISHED_PERCENT: f64 = 98.0;
+
+/// Kodi on another device, driven through its JSON-RPC API.
+pub struct Kodi {
+    settings: RemoteSettings,
+}
+
+impl Kodi {
+    pub fn new(settings: &RemoteSettings) -> Self {
+        Self {
+            settings: settings.clone(),
+        }
+    }
+
+    fn call(&self, method: &str, params: &Value) -> Result<Value, Box<dyn Error>> {
+        let settings = &self.settings;
+        let http = Http::new(
+            &settings.host,
+            settings.port,
+            &settings.username,
+            &settings.password,
+        );
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let response = http.post("/jsonrpc", &request)?;
+
+        if let Some(error) = response.get("error") {
+            let message = error["message"].as_str().unwrap_or("unknown error");
+            return Err(format!("Kodi: {method} failed: {message}").into());
+        }
+        Ok(response["result"].clone())
+    }
+
+    /// Position in seconds and percentage of the video being played, if any.
+    fn status(&self) -> Result<Option<(u64, f64)>, Box<dyn Error>> {
+        let players = self.call("Player.GetActivePlayers", &json!({}))?;
+        let Some(id) = players
+            .as_array()
+            .and_then(|players| players.iter().find(|player| player["type"] == "video"))
+            .and_then(|player| player["playerid"].as_i64())
+        else {
+            return Ok(None);
+        };
+
+        let properties = self.call(
+            "Player.GetProperties",
+            &json!({ "playerid": id, "properties": ["time", "percentage"] }),
+        )?;
+        let time = &properties["time"];
+        let seconds = |field: &str| time[field].as_u64().unwrap_or(0);
+        let position = seconds("hours") * 3600 + seconds("minutes") * 60 + seconds("seconds");
+
+        Ok(Some((
+            position,
+            properties["percentage"].as_f64().unwrap_or(0.0),
+        )))
+    }
+}
+
+impl PlayerBackend for Kodi {
+    /// Opens the files on Kodi, parts of a movie being stacked so that they
+    /// play as one.
+    fn play(&self, request: &PlayRequest) -> Result<Box<dyn Playing>, Box<dyn Error>> {
+        let files: Vec<String> = request
+            .files
+            .iter()
+            .map(|file| self.settings.remote_path(file))
+            .collect();
+        let file = match files.as_slice() {
+            [] => return Err("Nothing to play".into()),
+            [file] => file.clone(),
+            parts => format!("stack://{}", parts.join(" , ")),
+        };
+
+        let mut params = json!({ "item": { "file": file } });
+        if let Some(start) = request.start {
+            params["options"] = json!({ "resume": {
+                "hours": start / 3600,
+                "minutes": start / 60 % 60,
+                "seconds": start % 60,
+                "milliseconds": 0,
+            } });
+        }
+        self.call("Player.Open", &params)?;
+
+        Ok(Box::new(Self::new(&self.settings)))
+    }
+}
+
+impl Playing for Kodi {
+    /// Polls the position until Kodi stops playing. If it cannot be reached
+    /// anymore, the last position known is kept.
+    fn wait(
+        self: Box<Self>,
+        progress: &mut dyn FnMut(u64),
+    ) -> Result<Option<Playback>, Box<dyn Error>> {
+        let started = Instant::now();
+        let mut playback: Option<Playback> = None;
+        let mut failures = 0;
+
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let status = match self.status() {
+                Ok(status) => {
+                    failures = 0;
+                    status
+                }
+                Err(_) if failures < MAX_FAILED_POLLS => {
+                    failures += 1;
+                    continue;
+                }
+                Err(_) if playback.is_some() => break,
+                Err(e) => return Err(e),
+            };
+            match (status, &mut playback) {
+                (Some((position, percent)), playback) => {
+                    if playback.as_ref().is_none_or(|p| p.position != position) {
+                        progress(position);
+                    }
+                    *playback = Some(Playback {
+                        position,
+                        finished: percent >= FINISHED_PERCENT,
+                    });
+                }
+                (None, Some(_)) => break,
+                (None, None) if started.elapsed() > START_TIMEOUT => {
+                    return Err("Kodi did not start playing".into());
+                }
+                (None, None) => {}
+            }
+        }
+
+        Ok(playback)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::{http::mock, tracks::TrackChoices};
+
+    fn kodi(port: u16) -> Kodi {
+        Kodi::new(&RemoteSettings {
+            host: "127.0.0.1".to_string(),
+            port,
+            path_map: [(PathBuf::from("/mnt/nas"), "smb://nas".to_string())].into(),
+            ..RemoteSettings::default()
+        })
+    }
+
+    fn body(request: &str) -> Value {
+        serde_json::from_str(request.split_once("\r\n\r\n").unwrap().1).unwrap()
+    }
+
+    #[test]
+    fn play_opens_stacked_parts_with_resume_position() {
+        let (port, server) = mock::serve(vec![(200, json!({ "result": "OK" }).to_string())]);
+        let files = [
+            PathBuf::from("/mnt/nas/Movie/cd1.avi"),
+            PathBuf::from("/mnt/nas/Movie/cd2.avi"),
+        ];
+
+        kodi(port)
+            .play(&PlayRequest {
+                path: files[0].clone(),
+                files: files.to_vec(),
+                start: Some(3723),
+                tracks: TrackChoices::default(),
+                disc: None,
+            })
+            .unwrap();
+
+        let request = body(&server.join().unwrap()[0]);
+        assert_eq!(request["method"], "Player.Open");
+        assert_eq!(
+            request["params"]["item"]["file"],
+            "stack://smb://nas/Movie/cd1.avi , smb://nas/Movie/cd2.avi"
+        );
+        assert_eq!(
+            request["params"]["options"]["resume"],
+            json!({ "hours": 1, "minutes": 2, "seconds": 3, "milliseconds": 0 })
+        );
+    }
+
+    #[test]
+    fn play_reports_errors() {
+        let error = json!({ "error": { "code": -32602, "message": "Invalid params." } });
+        let (port, _server) = mock::serve(vec![(200, error.to_string())]);
+        let files = [PathBuf::from("/mnt/nas/Movie.mkv")];
+
+        let Err(error) = kodi(port).play(&PlayRequest {
+            path: files[0].clone(),
+            files: files.to_vec(),
+            start: None,
+            tracks: TrackChoices::default(),
+            disc: None,
+        }) else {
+            panic!("Player.Open should have failed");
+        };
+
+        assert_eq!(
+            error.to_string(),
+            "Kodi: Player.Open failed: Invalid params."
+        );
+    }
+
+    #[test]
+    fn wait_polls_until_kodi_stops() {
+        let players = json!({ "result": [{ "playerid": 1, "type": "video" }] });
+        let properties = json!({ "result": {
+            "time": { "hours": 1, "minutes": 0, "seconds": 5 },
+            "percentage": 99.0,
+        } });
+        let stopped = json!({ "result": [] });
+        let (port, server) = mock::serve(vec![
+            (200, players.to_string()),
+            (200, properties.to_string()),
+            (200, stopped.to_string()),
+        ]);
+
+        let mut positions = Vec::new();
+        let playback = Box::new(kodi(port))
+            .wait(&mut |position| positions.push(position))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(positions, [3605]);
+        assert_eq!(playback.position, 3605);
+        assert!(playback.finished);
+        let requests = server.join().unwrap();
+        assert_eq!(body(&requests[1])["params"]["playerid"], 1);
+    }
+
+    #[test]
+    fn wait_keeps_the_last_position_when_kodi_is_gone() {
+        let players = json!({ "result": [{ "playerid": 1, "type": "video" }] });
+        let properties = json!({ "result": {
+            "time": { "hours": 0, "minutes": 10, "seconds": 0 },
+            "percentage": 50.0,
+        } });
+        // Every query fails once the server is done
+        let (port, _server) = mock::serve(vec![
+            (200, players.to_string()),
+            (200, properties.to_string()),
+        ]);
+
+        let playback = Box::new(kodi(port)).wait(&mut |_| {}).unwrap().unwrap();
+
+        assert_eq!(playback.position, 600);
+        assert!(!playback.finished);
+    }
+}
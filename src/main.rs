@@ -1,16 +1,107 @@
 mod archive;
+mod artwork;
+mod base64;
+mod budget;
 mod collector;
+mod config;
+mod contact_sheet;
+mod credits;
+mod disc;
+mod duplicates;
+mod fnv;
+mod fuzzy;
+mod graphics;
+mod http;
+mod kodi;
 mod movie;
+mod mpv;
+mod part;
+mod player;
+mod query;
+mod series;
+mod session;
+mod sort;
+mod subtitles;
+mod surprise;
+mod table;
+mod title;
+mod tracks;
+mod transcoder;
 mod tui;
+mod verifier;
+mod vlc;
+mod watch_later;
 
-use std::{env, error::Error, path::PathBuf};
+use std::{
+    env,
+    error::Error,
+    path::{Path, PathBuf},
+};
 
-use crate::tui::app::App;
+use crate::{
+    archive::Archive,
+    budget::TimeBudget,
+    collector::Collector,
+    config::Config,
+    contact_sheet::ContactSheet,
+    credits::Credits,
+    duplicates::DuplicateReport,
+    movie::Movie,
+    query::{Entry, Query},
+    subtitles::Subtitles,
+    surprise::Surprise,
+    tui::app::App,
+};
+
+const USAGE: &str = "Usage: cineteca [path] #defaults to current dir
+       cineteca -d, --duplicates [path] #list byte-identical files
+       cineteca -c, --contact-sheets [path] #generate missing contact sheets
+       cineteca -s, --subtitles [path] [languages] #extract text subtitles, e.g. eng,spa
+       cineteca -e, --credits [path] #detect where the end credits begin
+       cineteca -r, --surprise [path] [constraints] #pick a random movie not watched yet
+           constraints: length=<minutes> tag=<tag> years=<n> older
+       cineteca -l, --list [path] [query|@smart list] #list what matches, e.g. unwatched length<2h";
 
 fn main() -> Result<(), Box<dyn Error>> {
-    match env::args().nth(1).as_deref().unwrap_or(".") {
+    let mut args = env::args().skip(1);
+
+    TimeBudget::init_local_offset();
+    Collector::ffmpeg_init().map_err(|e| format!("Failed to initialize ffmpeg: {e}"))?;
+    Config::init()?;
+
+    match args.next().as_deref().unwrap_or(".") {
         "-h" | "--help" => {
-            println!("Usage: cineteca [path] #defaults to current dir");
+            println!("{USAGE}");
+        }
+        "-d" | "--duplicates" => {
+            print!(
+                "{}",
+                DuplicateReport::scan(&load_archive(args.next().as_deref())?)
+            );
+        }
+        "-c" | "--contact-sheets" => {
+            generate_contact_sheets(&load_archive(args.next().as_deref())?);
+        }
+        "-s" | "--subtitles" => {
+            let archive = load_archive(args.next().as_deref())?;
+            let languages: Vec<String> = args
+                .next()
+                .map(|list| list.split(',').map(str::to_owned).collect())
+                .unwrap_or_default();
+            extract_subtitles(&archive, &languages);
+        }
+        "-e" | "--credits" => {
+            detect_credits(&mut load_archive(args.next().as_deref())?)?;
+        }
+        "-l" | "--list" => {
+            let (path, query) = split_path(args.collect());
+            let archive = load_archive(path.as_deref())?;
+            list(&archive, &query.join(" "))?;
+        }
+        "-r" | "--surprise" => {
+            let (path, constraints) = split_path(args.collect());
+            let mut archive = load_archive(path.as_deref())?;
+            surprise(&mut archive, constraints)?;
         }
         input => {
             App::run(&PathBuf::from(input).canonicalize()?);
@@ -18,3 +109,188 @@ fn main() -> Result<(), Box<dyn Error>> {
     }
     Ok(())
 }
+
+/// Splits the library path off the arguments of a command that takes more
+/// after it. The first one is only the path if it is an existing directory,
+/// so that e.g. `-l unwatched length<2h` lists the current one.
+fn split_path(mut args: Vec<String>) -> (Option<String>, Vec<String>) {
+    if args.first().is_some_and(|arg| Path::new(arg).is_dir()) {
+        let path = args.remove(0);
+        (Some(path), args)
+    } else {
+        (None, args)
+    }
+}
+
+/// Loads the library at `path`, updated with any changes on disk.
+fn load_archive(path: Option<&str>) -> Result<Archive, Box<dyn Error>> {
+    let path = PathBuf::from(path.unwrap_or(".")).canonicalize()?;
+    // A library opened for the first time is collected when created
+    let archive = Archive::load(&path).map_or_else(
+        || Archive::init(&path),
+        |mut archive| {
+            let (movies, series, hash) = Collector::collect(&path);
+            archive.update(movies, series, hash);
+            archive
+        },
+    );
+    archive.save()?;
+
+    Ok(archive)
+}
+
+fn generate_contact_sheets(archive: &Archive) {
+    for movie in archive.movies.iter().filter(|movie| !movie.is_disc()) {
+        if ContactSheet::find(movie).is_some() {
+            continue;
+        }
+        match ContactSheet::generate(movie) {
+            Ok(path) => println!("{}: {}", movie.name(), path.display()),
+            Err(e) => eprintln!("{}: {e}", movie.name()),
+        }
+    }
+}
+
+/// Picks a random movie meeting the configured constraints, overridden by
+/// `key=value` arguments. Watched movies are only candidates when `years`
+/// says how long ago they may have been watched.
+fn surprise(archive: &mut Archive, args: Vec<String>) -> Result<(), Box<dyn Error>> {
+    let mut settings = Config::get().surprise.clone();
+    for arg in args {
+        match arg.split_once('=') {
+            Some(("length", minutes)) => settings.max_length = Some(minutes.parse()?),
+            Some(("tag", tag)) => settings.tags.push(tag.to_owned()),
+            Some(("years", years)) => settings.not_watched_years = Some(years.parse()?),
+            None if arg == "older" => settings.favor_older = true,
+            _ => return Err(format!("Unknown constraint: {arg}\n{USAGE}").into()),
+        }
+    }
+
+    let movie = Surprise::pick(&archive.movies, &settings, archive.recent_picks())
+        .ok_or("No movie meets the constraints")?;
+    println!("{} ({})", movie.name(), movie.pretty_length());
+
+    let name = movie.name().to_owned();
+    archive.record_pick(&name, settings.recent_picks);
+    archive.save()
+}
+
+/// Prints the movies and series matching a query, or a saved one given as
+/// `@name`, with their length and when they were watched.
+fn list(archive: &Archive, text: &str) -> Result<(), Box<dyn Error>> {
+    let text = match text.strip_prefix('@') {
+        Some(name) => archive
+            .smart_lists()
+            .get(name)
+            .ok_or_else(|| format!("No smart list named {name}"))?,
+        None => text,
+    };
+    let query = (!text.trim().is_empty())
+        .then(|| Query::parse(text))
+        .transpose()?;
+
+    let movies = archive.movies.iter().map(Entry::Movie);
+    let series = archive.series.iter().map(Entry::Series);
+    let mut entries: Vec<Entry> = movies
+        .chain(series)
+        .filter(|entry| query.as_ref().is_none_or(|query| query.matches(*entry)))
+        .collect();
+    entries.sort_by(|a, b| sort::natural_cmp(a.name(), b.name()));
+
+    for entry in entries {
+        let (length, watched) = match entry {
+            Entry::Movie(movie) => (movie.pretty_length(), movie.pretty_since_watched()),
+            Entry::Series(series) => (
+                format!(
+                    "{}/{} episodes",
+                    series.num_watched(),
+                    series.num_episodes()
+                ),
+                movie::pretty_since_watched(series.since_watched()),
+            ),
+        };
+        println!("{}\t{length}\t{watched}", entry.name());
+    }
+    Ok(())
+}
+
+/// Looks for the credits of every movie that has not been analysed yet,
+/// saving the library as it goes.
+fn detect_credits(archive: &mut Archive) -> Result<(), Box<dyn Error>> {
+    let movies: Vec<Movie> = archive
+        .movies
+        .iter()
+        .filter(|movie| !movie.is_disc() && movie.credits_start().is_none())
+        .cloned()
+        .collect();
+
+    for movie in movies {
+        match Credits::detect(&movie) {
+            Ok(start) => {
+                println!("{}: {}", movie.name(), movie::pretty_position(start));
+                archive.set_credits_start(movie.name(), start);
+                archive.save()?;
+            }
+            Err(e) => eprintln!("{}: {e}", movie.name()),
+        }
+    }
+    Ok(())
+}
+
+/// Writes the text subtitles of every movie file to sidecar files, only
+/// those in the given languages unless the list is empty.
+fn extract_subtitles(archive: &Archive, languages: &[String]) {
+    let movie_files = archive
+        .movies
+        .iter()
+        .filter(|movie| !movie.is_disc())
+        .flat_map(Movie::paths);
+
+    for path in movie_files {
+        let tracks = Subtitles::tracks(path).map(|tracks| {
+            tracks
+                .into_iter()
+                .filter(|track| {
+                    languages.is_empty() || languages.iter().any(|l| l == track.language())
+                })
+                .collect::<Vec<_>>()
+        });
+        match tracks.and_then(|tracks| Subtitles::extract(path, &tracks)) {
+            Ok(sidecars) => sidecars.iter().for_each(|s| println!("{}", s.display())),
+            Err(e) => eprintln!("{}: {e}", path.display()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|&arg| arg.to_owned()).collect()
+    }
+
+    #[test]
+    fn split_path_takes_only_an_existing_directory() {
+        let dir = env::temp_dir().to_string_lossy().into_owned();
+
+        assert_eq!(
+            split_path(args(&[&dir, "length=100", "older"])),
+            (Some(dir.clone()), args(&["length=100", "older"]))
+        );
+        assert_eq!(
+            split_path(args(&["length=100", "older"])),
+            (None, args(&["length=100", "older"]))
+        );
+        assert_eq!(split_path(args(&["older"])), (None, args(&["older"])));
+        assert_eq!(
+            split_path(args(&["unwatched", "length<2h"])),
+            (None, args(&["unwatched", "length<2h"]))
+        );
+        assert_eq!(
+            split_path(args(&[&dir, "@weekend"])),
+            (Some(dir.clone()), args(&["@weekend"]))
+        );
+        assert_eq!(split_path(Vec::new()), (None, Vec::new()));
+    }
+}
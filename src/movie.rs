@@ -1,18 +1,41 @@
 use std::{
+    collections::BTreeSet,
     error::Error,
+    fs,
+    hash::{Hash, Hasher},
     num::NonZero,
     path::{Path, PathBuf},
     time::SystemTime,
 };
 
+use ffmpeg_next::{codec, format::context::Input, media};
 use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use crate::{
+    disc::{Disc, DiscKind},
+    fnv::Fnv,
+    part::Part,
+    session::Session,
+    title::Title,
+    tracks::TrackChoices,
+    verifier::Integrity,
+};
 
 const MIN_LENGTH: u64 = 3600;
-const EXTENSIONS: [&str; 4] = ["mkv", "mp4", "avi", "mov"];
+pub const EXTENSIONS: [&str; 4] = ["mkv", "mp4", "avi", "mov"];
+/// Highest rating a movie can be given.
+pub const MAX_RATING: u8 = 5;
 
 #[derive(Serialize, Deserialize, Eq, PartialEq, Clone, Hash)]
 pub struct MoviePath(PathBuf);
 
+impl MoviePath {
+    pub fn as_path(&self) -> &Path {
+        &self.0
+    }
+}
+
 impl TryFrom<&Path> for MoviePath {
     type Error = Box<dyn Error>;
 
@@ -28,16 +51,29 @@ impl TryFrom<&Path> for MoviePath {
 }
 
 #[derive(Serialize, Deserialize, Eq, PartialEq, Clone, Hash)]
-struct MovieLength(NonZero<u64>);
+pub struct MovieLength(NonZero<u64>);
+
+impl MovieLength {
+    /// Duration in seconds of a media file, without any validation.
+    pub fn probe(path: &Path) -> Result<u64, Box<dyn Error>> {
+        Ok(Self::from_input(&ffmpeg_next::format::input(&path)?))
+    }
+
+    pub fn from_input(input: &Input) -> u64 {
+        input.duration().cast_unsigned() / u64::from(ffmpeg_next::ffi::AV_TIME_BASE.cast_unsigned())
+    }
+}
 
 impl TryFrom<&Path> for MovieLength {
     type Error = Box<dyn Error>;
     fn try_from(path: &Path) -> Result<Self, Self::Error> {
-        let len: u64 = ffmpeg_next::format::input(&path)?
-            .duration()
-            .cast_unsigned()
-            / u64::from(ffmpeg_next::ffi::AV_TIME_BASE.cast_unsigned());
+        Self::try_from(Self::probe(path)?)
+    }
+}
 
+impl TryFrom<u64> for MovieLength {
+    type Error = Box<dyn Error>;
+    fn try_from(len: u64) -> Result<Self, Self::Error> {
         let valid_len = (len > MIN_LENGTH)
             .then_some(NonZero::<u64>::new(len).ok_or("Length cannot be zero")?)
             .ok_or("Length below minimum")?;
@@ -46,17 +82,437 @@ impl TryFrom<&Path> for MovieLength {
     }
 }
 
+#[derive(Serialize, Deserialize, Eq, PartialEq, Clone, Copy, Hash)]
+pub struct Resolution {
+    width: u32,
+    height: u32,
+}
+
+impl Resolution {
+    fn from_input(input: &Input) -> Option<Self> {
+        let stream = input.streams().best(media::Type::Video)?;
+        let video = codec::context::Context::from_parameters(stream.parameters())
+            .and_then(|context| context.decoder().video())
+            .ok()?;
+
+        Some(Self {
+            width: video.width(),
+            height: video.height(),
+        })
+    }
+
+    pub const fn pixels(self) -> u64 {
+        self.width as u64 * self.height as u64
+    }
+
+    /// Resolution as commonly named, e.g. `1080p` for 1920x1080 and
+    /// 1920x800 alike.
+    pub fn pretty(self) -> String {
+        match self.width {
+            3200.. => "2160p".to_string(),
+            1600.. => "1080p".to_string(),
+            1200.. => "720p".to_string(),
+            _ => format!("{}p", self.height),
+        }
+    }
+}
+
+pub fn pretty_length(length: u64) -> String {
+    let minutes = length / 60 % 60 + 1;
+    let hours = length / 3600;
+
+    match (hours, minutes) {
+        (0, 1..60) => format!("{minutes}m"),
+        (_, 0) => format!("{hours}h"),
+        (_, 1..60) => format!("{hours}h {minutes}m"),
+        (_, 60) => format!("{}h", hours + 1),
+        _ => String::new(),
+    }
+}
+
+/// Position within a movie, e.g. `1:02:03`.
+pub fn pretty_position(position: u64) -> String {
+    format!(
+        "{}:{:02}:{:02}",
+        position / 3600,
+        position / 60 % 60,
+        position % 60
+    )
+}
+
+/// Bar showing how much of something was played, e.g. `[█████░░░░░] 52%`,
+/// narrow enough for the side panel.
+pub fn pretty_progress(position: u64, length: u64) -> String {
+    const WIDTH: u64 = 10;
+
+    let percent = (position * 100 / length.max(1)).min(100);
+    let filled = percent * WIDTH / 100;
+    format!(
+        "[{}{}] {percent}%",
+        "█".repeat(usize::try_from(filled).unwrap_or_default()),
+        "░".repeat(usize::try_from(WIDTH - filled).unwrap_or_default())
+    )
+}
+
+/// Rating as stars, e.g. `★★★☆☆`.
+pub fn pretty_rating(rating: u8) -> String {
+    let rating = rating.min(MAX_RATING);
+    "★".repeat(rating.into()) + &"☆".repeat((MAX_RATING - rating).into())
+}
+
+pub fn pretty_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+
+    let mut size = bytes;
+    let mut unit = 0;
+    while size >= 1024 * 1024 && unit < UNITS.len() - 2 {
+        size /= 1024;
+        unit += 1;
+    }
+
+    match size {
+        0..1024 => format!("{size} {}", UNITS[unit]),
+        _ => format!(
+            "{}.{} {}",
+            size / 1024,
+            size % 1024 * 10 / 1024,
+            UNITS[unit + 1]
+        ),
+    }
+}
+
+/// Last modification time of a file or folder, if the system records it.
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Size on disk of a file, or of every file in a folder.
+fn disk_size(path: &Path) -> u64 {
+    WalkDir::new(path)
+        .into_iter()
+        .filter_map(|entry| entry.ok()?.metadata().ok())
+        .filter(fs::Metadata::is_file)
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+pub fn pretty_since_watched(since_watched: Option<SystemTime>) -> String {
+    since_watched.map_or_else(
+        || "Not yet".to_string(),
+        |time| {
+            let hours_since = SystemTime::now()
+                .duration_since(time)
+                .expect("Error calculating duration")
+                .as_secs()
+                / 3600;
+
+            match hours_since {
+                0 => "<1h ago".to_string(),
+                1 => "1h ago".to_string(),
+                2..24 => format!("{hours_since}h ago"),
+                24.. => format!("{}d ago", hours_since / 24),
+            }
+        },
+    )
+}
+
 #[derive(Serialize, Deserialize, Eq, PartialEq, Clone, Hash)]
 pub struct Movie {
     name: String,
     path: MoviePath,
     length: MovieLength,
     since_watched: Option<SystemTime>,
+    /// Remaining files of a movie split into several parts, in play order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    parts: Vec<MoviePath>,
+    /// Set when `path` points to a disc folder or ISO image rather than a file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    disc: Option<DiscKind>,
+    #[serde(default)]
+    size: u64,
+    #[serde(default)]
+    resolution: Option<Resolution>,
+    /// Other copies of the same movie, e.g. in a different resolution. They
+    /// share the name and watch history of the preferred one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    versions: Vec<Self>,
+    /// Result of the last verification, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    integrity: Option<Integrity>,
+    /// Copy of the file converted to the transcode profile, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    transcoded: Option<PathBuf>,
+    /// Seconds into the movie at which the end credits begin, once detected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    credits_start: Option<u64>,
+    /// Audio and subtitle tracks to play the file with
+    #[serde(default, skip_serializing_if = "TrackChoices::is_auto")]
+    tracks: TrackChoices,
+    /// Seconds into the movie at which the last playback was stopped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    resume_position: Option<u64>,
+    /// When the resume position was last saved or cleared, to tell whether a
+    /// position saved by mpv is newer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    resume_saved: Option<SystemTime>,
+    /// Every time the movie was played, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    sessions: Vec<Session>,
+    /// From 1 to `MAX_RATING`, given after watching it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rating: Option<u8>,
+    /// Labels given by the user, e.g. a genre, in lowercase
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    tags: BTreeSet<String>,
+    /// When the movie was added to the library, taken from the modification
+    /// time of its file when it was first found
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    added: Option<SystemTime>,
 }
 
 impl Movie {
-    pub const fn path(&self) -> &PathBuf {
-        &self.path.0
+    /// Movie just found in the library, with the size and modification time
+    /// of its main file and no history.
+    fn new(name: String, path: MoviePath, length: MovieLength) -> Self {
+        Self {
+            name,
+            size: disk_size(&path.0),
+            added: modified(&path.0),
+            path,
+            length,
+            since_watched: None,
+            parts: Vec::new(),
+            disc: None,
+            resolution: None,
+            versions: Vec::new(),
+            integrity: None,
+            transcoded: None,
+            credits_start: None,
+            tracks: TrackChoices::default(),
+            resume_position: None,
+            resume_saved: None,
+            sessions: Vec::new(),
+            rating: None,
+            tags: BTreeSet::new(),
+        }
+    }
+
+    /// Every file of the movie, in play order.
+    pub fn paths(&self) -> impl Iterator<Item = &PathBuf> {
+        std::iter::once(&self.path.0).chain(self.parts.iter().map(|part| &part.0))
+    }
+
+    /// Identifier derived from the main file, used to name cached files.
+    pub fn id(&self) -> String {
+        let mut hasher = Fnv::default();
+        hasher.write(self.path.0.as_os_str().as_encoded_bytes());
+        format!("{:016x}", hasher.finish())
+    }
+
+    pub const fn length(&self) -> u64 {
+        self.length.0.get()
+    }
+
+    pub const fn credits_start(&self) -> Option<u64> {
+        self.credits_start
+    }
+
+    pub const fn set_credits_start(&mut self, credits_start: u64) {
+        self.credits_start = Some(credits_start);
+    }
+
+    pub const fn tracks(&self) -> TrackChoices {
+        self.tracks
+    }
+
+    pub const fn set_tracks(&mut self, tracks: TrackChoices) {
+        self.tracks = tracks;
+    }
+
+    pub const fn resume_position(&self) -> Option<u64> {
+        self.resume_position
+    }
+
+    pub const fn resume_saved(&self) -> Option<SystemTime> {
+        self.resume_saved
+    }
+
+    pub fn set_resume_position(&mut self, resume_position: Option<u64>) {
+        self.resume_position = resume_position;
+        self.resume_saved = Some(SystemTime::now());
+    }
+
+    pub fn sessions(&self) -> &[Session] {
+        &self.sessions
+    }
+
+    pub fn add_session(&mut self, session: Session) {
+        self.sessions.push(session);
+    }
+
+    pub const fn rating(&self) -> Option<u8> {
+        self.rating
+    }
+
+    pub fn set_rating(&mut self, rating: Option<u8>) {
+        self.rating = rating.map(|rating| rating.clamp(1, MAX_RATING));
+    }
+
+    /// Replaces the tags, which are stored in lowercase.
+    pub fn set_tags(&mut self, tags: impl IntoIterator<Item = String>) {
+        self.tags = tags
+            .into_iter()
+            .map(|tag| tag.trim().to_lowercase())
+            .filter(|tag| !tag.is_empty())
+            .collect();
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.contains(&tag.to_lowercase())
+    }
+
+    pub const fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Release year, when the file name has one.
+    pub fn year(&self) -> Option<u16> {
+        Title::from(self.name.as_str()).year()
+    }
+
+    pub const fn added(&self) -> Option<SystemTime> {
+        self.added
+    }
+
+    /// Whether the movie was started and stopped before the end.
+    pub const fn is_in_progress(&self) -> bool {
+        self.resume_position.is_some()
+    }
+
+    /// Length without the end credits, when they have been detected.
+    pub fn effective_length(&self) -> u64 {
+        let length = self.length();
+        self.credits_start
+            .filter(|&start| start > 0)
+            .map_or(length, |start| start.min(length))
+    }
+
+    /// Seconds left to watch up to the credits, from where the last playback
+    /// was stopped.
+    pub fn remaining_length(&self) -> u64 {
+        self.effective_length()
+            .saturating_sub(self.resume_position.unwrap_or_default())
+    }
+
+    pub const fn num_parts(&self) -> usize {
+        self.parts.len() + 1
+    }
+
+    pub const fn is_disc(&self) -> bool {
+        self.disc.is_some()
+    }
+
+    pub const fn disc(&self) -> Option<DiscKind> {
+        self.disc
+    }
+
+    pub const fn resolution(&self) -> Option<Resolution> {
+        self.resolution
+    }
+
+    pub fn versions(&self) -> &[Self] {
+        &self.versions
+    }
+
+    pub const fn integrity(&self) -> Option<&Integrity> {
+        self.integrity.as_ref()
+    }
+
+    pub fn set_integrity(&mut self, integrity: Integrity) {
+        self.integrity = Some(integrity);
+    }
+
+    pub fn transcoded(&self) -> Option<&Path> {
+        self.transcoded.as_deref()
+    }
+
+    /// Links the converted copy to whichever version `path` is the main file
+    /// of.
+    pub fn set_transcoded(&mut self, path: &Path, transcoded: PathBuf) {
+        if self.path.as_path() == path {
+            self.transcoded = Some(transcoded);
+        } else if let Some(version) = self
+            .versions
+            .iter_mut()
+            .find(|version| version.path.as_path() == path)
+        {
+            version.transcoded = Some(transcoded);
+        }
+    }
+
+    pub const fn is_broken(&self) -> bool {
+        matches!(self.integrity, Some(Integrity::Broken { .. }))
+    }
+
+    /// Groups copies of the same movie into one, preferring the one with the
+    /// highest resolution and then the biggest file.
+    pub fn with_versions(mut versions: Vec<Self>) -> Option<Self> {
+        versions.sort_by_key(|movie| {
+            std::cmp::Reverse((movie.resolution.map(Resolution::pixels), movie.size))
+        });
+
+        let mut movie = (!versions.is_empty()).then(|| versions.remove(0))?;
+        movie.versions = versions;
+        Some(movie)
+    }
+
+    /// Makes the next version the preferred one, keeping the entry's name and
+    /// watch history.
+    pub fn cycle_version(&mut self) {
+        if self.versions.is_empty() {
+            return;
+        }
+
+        let next = self.versions.remove(0);
+        let mut previous = std::mem::replace(self, next);
+
+        self.name = std::mem::take(&mut previous.name);
+        self.since_watched = previous.since_watched.take();
+        self.resume_position = previous.resume_position.take();
+        self.resume_saved = previous.resume_saved.take();
+        self.sessions = std::mem::take(&mut previous.sessions);
+        self.rating = previous.rating.take();
+        self.tags = std::mem::take(&mut previous.tags);
+        self.added = previous.added.take().or(self.added);
+        self.versions = std::mem::take(&mut previous.versions);
+
+        previous.name = previous
+            .path
+            .0
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        self.versions.push(previous);
+    }
+
+    /// Path to hand over to the player. Multi-part movies are written to an
+    /// M3U playlist so that the parts are played one after another, and discs
+    /// are given by their folder or image.
+    pub fn play_path(&self) -> Result<PathBuf, Box<dyn Error>> {
+        if self.parts.is_empty() {
+            return Ok(self.path.0.clone());
+        }
+
+        let playlist = self
+            .paths()
+            .map(|path| path.to_string_lossy().into_owned() + "\n")
+            .collect::<String>();
+        let playlist_path = std::env::temp_dir().join(format!("cineteca-{}.m3u", self.name));
+        fs::write(&playlist_path, "#EXTM3U\n".to_owned() + &playlist)?;
+
+        Ok(playlist_path)
     }
 
     pub fn name(&self) -> &str {
@@ -67,47 +523,92 @@ impl Movie {
         self.since_watched
     }
 
-    pub fn pretty_length(&self) -> String {
-        let minutes = self.length.0.get() / 60 % 60 + 1;
-        let hours = self.length.0.get() / 3600;
-
-        match minutes {
-            0 => format!("{hours}h"),
-            1..60 => format!("{hours}h {minutes}m"),
-            60 => format!("{}h", hours + 1),
-            _ => String::new(),
+    /// Paths of the main file of every version.
+    pub fn version_paths(&self) -> impl Iterator<Item = &Path> {
+        std::iter::once(self)
+            .chain(&self.versions)
+            .map(|v| v.path.as_path())
+    }
+
+    /// Carries the watch history, rating, tags and preferred version over from the entries
+    /// of a previous scan that share files with this movie.
+    pub fn restore(&mut self, old: &[&Self]) {
+        self.since_watched = old.iter().filter_map(|movie| movie.since_watched).max();
+        self.resume_position = old.iter().find_map(|movie| movie.resume_position);
+        self.resume_saved = old.iter().filter_map(|movie| movie.resume_saved).max();
+        self.rating = old.iter().find_map(|movie| movie.rating);
+        self.tags = old.iter().flat_map(|movie| &movie.tags).cloned().collect();
+        self.added = old
+            .iter()
+            .filter_map(|movie| movie.added)
+            .min()
+            .or(self.added);
+        self.sessions = old
+            .iter()
+            .flat_map(|movie| &movie.sessions)
+            .copied()
+            .collect();
+        self.sessions.sort_by_key(Session::start);
+
+        let preferred = old.iter().find_map(|movie| {
+            self.versions
+                .iter()
+                .position(|version| version.path == movie.path)
+        });
+        if let Some(index) = preferred {
+            for _ in 0..=index {
+                self.cycle_version();
+            }
         }
+
+        // Verification results, converted copies, credits and tracks belong
+        // to the file, not to the entry
+        let old_versions: Vec<&Self> = old
+            .iter()
+            .flat_map(|movie| std::iter::once(*movie).chain(&movie.versions))
+            .collect();
+        let restore_file = |version: &mut Self| {
+            let old = old_versions.iter().find(|old| old.path == version.path);
+            version.integrity = old.and_then(|old| old.integrity.clone());
+            version.transcoded = old
+                .and_then(|old| old.transcoded.clone())
+                .filter(|path| path.exists());
+            version.credits_start = old.and_then(|old| old.credits_start);
+            version.tracks = old.map(|old| old.tracks).unwrap_or_default();
+        };
+        restore_file(self);
+        for version in &mut self.versions {
+            restore_file(version);
+        }
+    }
+
+    pub fn pretty_length(&self) -> String {
+        pretty_length(self.effective_length())
     }
 
     pub fn pretty_since_watched(&self) -> String {
-        self.since_watched.map_or_else(
-            || "Not yet".to_string(),
-            |time| {
-                let hours_since = SystemTime::now()
-                    .duration_since(time)
-                    .expect("Error calculating duration")
-                    .as_secs()
-                    / 3600;
-
-                match hours_since {
-                    0 => "<1h ago".to_string(),
-                    1 => "1h ago".to_string(),
-                    2..24 => format!("{hours_since}h ago"),
-                    24.. => format!("{}d ago", hours_since / 24),
-                }
-            },
-        )
+        pretty_since_watched(self.since_watched)
+    }
+
+    pub fn pretty_tags(&self) -> String {
+        self.tags
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(", ")
     }
 
     pub fn toggle_watched(&mut self) {
         match self.since_watched {
             Some(_) => self.since_watched = None,
-            None => self.since_watched = Some(SystemTime::now()),
+            None => self.set_watched(),
         }
     }
 
+    /// Marks the movie as watched now, forgetting where it was stopped.
     pub fn set_watched(&mut self) {
         self.since_watched = Some(SystemTime::now());
+        self.resume_position = None;
     }
 }
 
@@ -121,11 +622,59 @@ impl TryFrom<&Path> for Movie {
             .to_string_lossy()
             .into_owned();
 
+        let movie_path = MoviePath::try_from(path)?;
+        let input = ffmpeg_next::format::input(&path)?;
+
+        let length = MovieLength::try_from(MovieLength::from_input(&input))?;
+
         Ok(Self {
-            name,
-            path: MoviePath::try_from(path)?,
-            length: MovieLength::try_from(path)?,
-            since_watched: None,
+            resolution: Resolution::from_input(&input),
+            ..Self::new(name, movie_path, length)
+        })
+    }
+}
+
+impl TryFrom<Vec<Part>> for Movie {
+    type Error = Box<dyn Error>;
+
+    fn try_from(mut parts: Vec<Part>) -> Result<Self, Self::Error> {
+        parts.sort_by_key(Part::number);
+
+        let first = parts.first().ok_or("Movie must have at least one part")?;
+        let name = first.movie_name();
+
+        let length = parts.iter().try_fold(0, |total, part| {
+            MovieLength::probe(part.path()).map(|len| total + len)
+        })?;
+
+        let mut paths = parts
+            .iter()
+            .map(|part| MoviePath::try_from(part.path().as_path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let path = paths.remove(0);
+        let resolution = ffmpeg_next::format::input(&path.0)
+            .ok()
+            .and_then(|input| Resolution::from_input(&input));
+
+        Ok(Self {
+            size: parts.iter().map(|part| disk_size(part.path())).sum(),
+            parts: paths,
+            resolution,
+            ..Self::new(name, path, MovieLength::try_from(length)?)
+        })
+    }
+}
+
+impl TryFrom<Disc> for Movie {
+    type Error = Box<dyn Error>;
+
+    fn try_from(disc: Disc) -> Result<Self, Self::Error> {
+        let path = MoviePath(disc.root().clone());
+        let length = MovieLength::try_from(disc.length())?;
+
+        Ok(Self {
+            disc: Some(disc.kind()),
+            ..Self::new(disc.name().to_owned(), path, length)
         })
     }
 }
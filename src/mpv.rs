@@ -0,0 +1,164 @@
+use std::{
+    error::Error,
+    io::{BufRead, BufReader, Write},
+    os::unix::net::UnixStream,
+    path::Path,
+    thread,
+    time::Duration,
+};
+
+use serde_json::{Value, json};
+
+use crate::player::Playback;
+
+/// How long mpv is given to create its IPC socket.
+const CONNECT_ATTEMPTS: u32 = 50;
+const CONNECT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Client of mpv's JSON IPC, started with `--input-ipc-server`.
+pub struct Mpv;
+
+impl Mpv {
+    /// Follows the playback until mpv exits, calling `progress` with the
+    /// position in seconds whenever it changes.
+    pub fn follow(
+        socket: &Path,
+        progress: &mut dyn FnMut(u64),
+    ) -> Result<Playback, Box<dyn Error>> {
+        let mut stream = Self::connect(socket)?;
+        writeln!(
+            stream,
+            "{}",
+            json!({ "command": ["observe_property", 1, "time-pos"] })
+        )?;
+
+        // Seconds played in the files of a playlist that already ended
+        let mut offset = 0.0;
+        let mut time_pos = 0.0;
+        let mut playback = Playback {
+            position: 0,
+            finished: false,
+        };
+
+        for line in BufReader::new(stream).lines() {
+            let Ok(message) = serde_json::from_str::<Value>(&line?) else {
+                continue;
+            };
+
+            match message["event"].as_str() {
+                Some("property-change") if message["name"] == "time-pos" => {
+                    let Some(seconds) = message["data"].as_f64() else {
+                        continue;
+                    };
+                    time_pos = seconds;
+                    let position = Duration::try_from_secs_f64(offset + time_pos)
+                        .map_or(0, |played| played.as_secs());
+                    if position != playback.position {
+                        playback.position = position;
+                        progress(position);
+                    }
+                }
+                Some("start-file") => playback.finished = false,
+                Some("end-file") if message["reason"] == "eof" => {
+                    offset += time_pos;
+                    time_pos = 0.0;
+                    playback.finished = true;
+                }
+                Some("shutdown") => break,
+                _ => {}
+            }
+        }
+
+        Ok(playback)
+    }
+
+    fn connect(socket: &Path) -> Result<UnixStream, Box<dyn Error>> {
+        for _ in 0..CONNECT_ATTEMPTS {
+            if let Ok(stream) = UnixStream::connect(socket) {
+                return Ok(stream);
+            }
+            thread::sleep(CONNECT_INTERVAL);
+        }
+        Err("Could not connect to mpv, is the player command running mpv?".into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, os::unix::net::UnixListener, path::PathBuf, process};
+
+    use super::*;
+
+    /// Socket of a fake mpv that answers the connection with `events`, one
+    /// JSON message per line, once it was asked to observe the position.
+    fn fake_mpv(name: &str, events: &[Value]) -> (PathBuf, thread::JoinHandle<String>) {
+        let socket = env::temp_dir().join(format!("cineteca-test-{}-{name}.sock", process::id()));
+        fs::remove_file(&socket).ok();
+        let listener = UnixListener::bind(&socket).unwrap();
+        let events: Vec<String> = events.iter().map(Value::to_string).collect();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut command = String::new();
+            BufReader::new(&stream).read_line(&mut command).unwrap();
+            for event in events {
+                writeln!(stream, "{event}").unwrap();
+            }
+            command
+        });
+        (socket, server)
+    }
+
+    fn time_pos(seconds: f64) -> Value {
+        json!({ "event": "property-change", "id": 1, "name": "time-pos", "data": seconds })
+    }
+
+    #[test]
+    fn follow_counts_every_file_of_a_playlist() {
+        let (socket, server) = fake_mpv(
+            "playlist",
+            &[
+                json!({ "event": "start-file" }),
+                time_pos(0.0),
+                json!({ "request_id": 0, "error": "success" }),
+                time_pos(599.6),
+                json!({ "event": "end-file", "reason": "eof" }),
+                json!({ "event": "start-file" }),
+                time_pos(10.5),
+                json!({ "event": "end-file", "reason": "eof" }),
+                json!({ "event": "shutdown" }),
+            ],
+        );
+
+        let mut positions = Vec::new();
+        let playback = Mpv::follow(&socket, &mut |position| positions.push(position)).unwrap();
+
+        assert_eq!(positions, [599, 610]);
+        assert_eq!(playback.position, 610);
+        assert!(playback.finished);
+        let command: Value = serde_json::from_str(&server.join().unwrap()).unwrap();
+        assert_eq!(
+            command["command"],
+            json!(["observe_property", 1, "time-pos"])
+        );
+        fs::remove_file(socket).ok();
+    }
+
+    #[test]
+    fn follow_reports_a_playback_stopped_early() {
+        let (socket, _server) = fake_mpv(
+            "stopped",
+            &[
+                time_pos(42.0),
+                json!({ "event": "end-file", "reason": "quit" }),
+                json!({ "event": "shutdown" }),
+            ],
+        );
+
+        let playback = Mpv::follow(&socket, &mut |_| {}).unwrap();
+
+        assert_eq!(playback.position, 42);
+        assert!(!playback.finished);
+        fs::remove_file(socket).ok();
+    }
+}
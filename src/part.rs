@@ -0,0 +1,137 @@
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+use crate::movie::MoviePath;
+
+const PREFIXES: [&str; 6] = ["part", "disc", "disk", "cd", "pt", "dvd"];
+/// Markers also naming the installments of a story, e.g. `Dune Part 2`.
+const STORY_PREFIXES: [&str; 2] = ["part", "pt"];
+const SEPARATORS: [char; 4] = [' ', '.', '_', '-'];
+
+/// A single file of a movie split across several files, e.g. `Movie.cd1.avi`.
+pub struct Part {
+    number: u32,
+    prefix: &'static str,
+    base: String,
+    extension: String,
+    path: PathBuf,
+}
+
+impl Part {
+    pub const fn number(&self) -> u32 {
+        self.number
+    }
+
+    pub const fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// Whether the marker could name a sequel rather than a piece of a
+    /// movie, as in `Deathly Hallows Part 1`.
+    pub fn may_be_sequel(&self) -> bool {
+        STORY_PREFIXES.contains(&self.prefix)
+    }
+
+    /// Name of the whole movie, i.e. the file name without the part marker.
+    pub fn movie_name(&self) -> String {
+        format!("{}.{}", self.base, self.extension)
+    }
+
+    /// Parts sharing a key belong to the same movie.
+    pub fn key(&self) -> (PathBuf, String) {
+        let dir = self
+            .path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        (dir, self.movie_name().to_lowercase())
+    }
+
+    /// Splits a file stem such as `Movie.cd1` or `Movie - Part 2` into its
+    /// base name, marker and part number.
+    fn split(stem: &str) -> Option<(&str, &'static str, u32)> {
+        let without_number = stem.trim_end_matches(|c: char| c.is_ascii_digit());
+        let number = stem[without_number.len()..].parse().ok()?;
+        let without_number = without_number.trim_end_matches(SEPARATORS);
+
+        let lowercase = without_number.to_ascii_lowercase();
+        let prefix = PREFIXES.iter().find(|p| lowercase.ends_with(*p))?;
+        let base = &without_number[..without_number.len() - prefix.len()];
+
+        // The marker must be a separate word, otherwise "Apart1" would match
+        (base.ends_with(SEPARATORS) || base.ends_with(['(', '[']))
+            .then(|| base.trim_end_matches(|c| SEPARATORS.contains(&c) || c == '(' || c == '['))
+            .filter(|base| !base.is_empty())
+            .map(|base| (base, *prefix, number))
+    }
+}
+
+impl TryFrom<&Path> for Part {
+    type Error = Box<dyn Error>;
+
+    fn try_from(path: &Path) -> Result<Self, Self::Error> {
+        MoviePath::try_from(path)?;
+
+        let stem = path
+            .file_stem()
+            .ok_or("Path does not have a valid filename")?
+            .to_string_lossy();
+        let extension = path
+            .extension()
+            .ok_or("Invalid or missing file extension")?
+            .to_string_lossy()
+            .into_owned();
+
+        // Allow a closing bracket after the number, e.g. "Movie (Part 1)"
+        let (base, prefix, number) = Self::split(stem.trim_end_matches([')', ']']))
+            .ok_or("File name does not contain a part marker")?;
+
+        Ok(Self {
+            number,
+            prefix,
+            base: base.to_owned(),
+            extension,
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_finds_the_marker_and_number() {
+        assert_eq!(Part::split("Movie.cd1"), Some(("Movie", "cd", 1)));
+        assert_eq!(Part::split("Movie - Part 2"), Some(("Movie", "part", 2)));
+        assert_eq!(Part::split("Movie_DVD02"), Some(("Movie", "dvd", 2)));
+        assert_eq!(Part::split("Movie (Disc 3"), Some(("Movie", "disc", 3)));
+        assert_eq!(Part::split("Movie pt.4"), Some(("Movie", "pt", 4)));
+    }
+
+    #[test]
+    fn split_needs_a_separate_marker_and_a_base() {
+        assert_eq!(Part::split("Apart1"), None);
+        assert_eq!(Part::split("Movie 2"), None);
+        assert_eq!(Part::split("Movie cd"), None);
+        assert_eq!(Part::split("Part 1"), None);
+    }
+
+    #[test]
+    fn parts_of_a_movie_share_a_key() {
+        let first = Part::try_from(Path::new("/movies/Movie (Part 1).mkv")).unwrap();
+        let second = Part::try_from(Path::new("/movies/movie.part2.mkv")).unwrap();
+
+        assert_eq!(first.number(), 1);
+        assert_eq!(first.movie_name(), "Movie.mkv");
+        assert!(first.key() == second.key());
+        assert!(first.may_be_sequel());
+        assert!(
+            !Part::try_from(Path::new("/movies/Movie.cd1.mkv"))
+                .unwrap()
+                .may_be_sequel()
+        );
+    }
+}
@@ -0,0 +1,547 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::Config,
+    disc::DiscKind,
+    kodi::Kodi,
+    mpv::Mpv,
+    tracks::{TrackChoice, TrackChoices},
+    vlc::VlcHttp,
+};
+
+/// Extensions of the subtitle files picked up next to a movie.
+const SUBTITLE_EXTENSIONS: [&str; 4] = ["srt", "ass", "vtt", "sub"];
+/// Programs that hand a file over to the default player and exit right away.
+const LAUNCHERS: [&str; 3] = ["xdg-open", "open", "gio"];
+
+/// How playback is followed once the player is started.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    /// Only the exit of the player is waited for, then the user is asked
+    /// whether the movie was watched
+    #[default]
+    Command,
+    /// The command runs mpv, whose position is followed through its IPC
+    /// socket
+    Mpv,
+    /// Kodi on another device, driven through its JSON-RPC API
+    Kodi,
+    /// VLC on another device, driven through its HTTP interface
+    Vlc,
+}
+
+/// Connection to a player on another device.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct RemoteSettings {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    /// Local folders and what the device calls them, e.g. `smb://nas/movies`
+    pub path_map: BTreeMap<PathBuf, String>,
+}
+
+impl RemoteSettings {
+    /// Path of a local file as seen from the device, through the deepest
+    /// matching folder of the path map.
+    pub fn remote_path(&self, path: &Path) -> String {
+        self.path_map
+            .iter()
+            .filter(|(local, _)| path.starts_with(local))
+            .max_by_key(|(local, _)| local.components().count())
+            .and_then(|(local, remote)| {
+                let rest = path
+                    .strip_prefix(local)
+                    .ok()?
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                Some(format!("{}/{rest}", remote.trim_end_matches('/')))
+            })
+            .unwrap_or_else(|| path.to_string_lossy().into_owned())
+    }
+}
+
+/// Commands used to play movies. Arguments can contain `{path}`, `{start}`
+/// (resume position in seconds) and `{sub}` (subtitle file next to the
+/// movie). Arguments whose placeholder has no value are left out.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct PlayerSettings {
+    /// Program followed by its arguments
+    pub command: Vec<String>,
+    /// Commands for files with the given extension
+    pub extensions: BTreeMap<String, Vec<String>>,
+    /// Commands for files under the given folder, the deepest one winning.
+    /// They take precedence over the ones by extension
+    pub roots: BTreeMap<PathBuf, Vec<String>>,
+    pub backend: Backend,
+    /// Percentage of a movie, up to its credits, that has to be played for it
+    /// to count as watched. Only used by the backends following the playback
+    pub watched_percent: u64,
+    /// Language codes of the audio tracks picked automatically, most
+    /// preferred first
+    pub audio_languages: Vec<String>,
+    /// Language codes of the subtitle tracks picked automatically, most
+    /// preferred first
+    pub subtitle_languages: Vec<String>,
+    pub kodi: RemoteSettings,
+    pub vlc: RemoteSettings,
+}
+
+impl Default for PlayerSettings {
+    fn default() -> Self {
+        Self {
+            command: vec!["xdg-open".to_string(), "{path}".to_string()],
+            extensions: BTreeMap::new(),
+            roots: BTreeMap::new(),
+            backend: Backend::default(),
+            watched_percent: 90,
+            audio_languages: Vec::new(),
+            subtitle_languages: Vec::new(),
+            kodi: RemoteSettings {
+                host: "localhost".to_string(),
+                port: 8080,
+                username: "kodi".to_string(),
+                ..RemoteSettings::default()
+            },
+            vlc: RemoteSettings {
+                host: "localhost".to_string(),
+                port: 8080,
+                ..RemoteSettings::default()
+            },
+        }
+    }
+}
+
+impl PlayerSettings {
+    /// Command template that applies to the file.
+    fn template(&self, file: &Path) -> &[String] {
+        let by_root = self
+            .roots
+            .iter()
+            .filter(|(root, _)| file.starts_with(root))
+            .max_by_key(|(root, _)| root.components().count())
+            .map(|(_, command)| command);
+        let by_extension = || {
+            file.extension()
+                .and_then(|ext| ext.to_str())
+                .and_then(|ext| self.extensions.get(&ext.to_lowercase()))
+        };
+
+        by_root.or_else(by_extension).unwrap_or(&self.command)
+    }
+
+    /// Whether playing up to `position` seconds of something `length` seconds
+    /// long is enough to call it watched.
+    pub const fn is_watched(&self, position: u64, length: u64) -> bool {
+        position * 100 >= length * self.watched_percent
+    }
+}
+
+/// Where a followed playback got to when it ended.
+pub struct Playback {
+    /// Seconds played, counting every finished file of a playlist
+    pub position: u64,
+    /// Whether the last file was played to its end
+    pub finished: bool,
+}
+
+/// A movie or episode to play.
+pub struct PlayRequest {
+    /// What a local player is given: the file, a playlist of the parts or a
+    /// disc location
+    pub path: PathBuf,
+    /// Files in play order, or the folder or image of a disc
+    pub files: Vec<PathBuf>,
+    /// Seconds to resume from
+    pub start: Option<u64>,
+    pub tracks: TrackChoices,
+    /// Set when `path` is the folder or image of a disc
+    pub disc: Option<DiscKind>,
+}
+
+/// A way of playing movies, on this machine or on another device.
+pub trait PlayerBackend {
+    fn play(&self, request: &PlayRequest) -> Result<Box<dyn Playing>, Box<dyn Error>>;
+}
+
+/// A playback started by a backend.
+pub trait Playing: Send {
+    /// Blocks until the playback ends, calling `progress` with the position
+    /// in seconds as it changes. Returns where it got to, or `None` when the
+    /// backend cannot tell.
+    fn wait(
+        self: Box<Self>,
+        progress: &mut dyn FnMut(u64),
+    ) -> Result<Option<Playback>, Box<dyn Error>>;
+
+    /// Launcher the movie was handed to, which exits without waiting for
+    /// the actual player.
+    fn launcher(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// Players whose track selection options are known.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Flavor {
+    Mpv,
+    Vlc,
+}
+
+impl Flavor {
+    fn of(program: &str) -> Option<Self> {
+        match Path::new(program).file_stem()?.to_str()? {
+            "mpv" => Some(Self::Mpv),
+            "vlc" | "cvlc" => Some(Self::Vlc),
+            _ => None,
+        }
+    }
+
+    /// Options selecting a track, which VLC counts from 0. Automatic choices
+    /// are left to the player, given the preferred languages if any.
+    fn track_args(self, choices: TrackChoices, settings: &PlayerSettings) -> Vec<String> {
+        let languages = |option: &str, languages: &[String]| {
+            (!languages.is_empty()).then(|| format!("--{option}={}", languages.join(",")))
+        };
+        let audio = match (self, choices.audio) {
+            (Self::Mpv, TrackChoice::Auto) => languages("alang", &settings.audio_languages),
+            (Self::Vlc, TrackChoice::Auto) => {
+                languages("audio-language", &settings.audio_languages)
+            }
+            (Self::Mpv, TrackChoice::Off) => Some("--aid=no".to_string()),
+            (Self::Mpv, TrackChoice::Track(number)) => Some(format!("--aid={number}")),
+            (Self::Vlc, TrackChoice::Off) => Some("--no-audio".to_string()),
+            (Self::Vlc, TrackChoice::Track(number)) => {
+                Some(format!("--audio-track={}", number - 1))
+            }
+        };
+        let subtitle = match (self, choices.subtitle) {
+            (Self::Mpv, TrackChoice::Auto) => languages("slang", &settings.subtitle_languages),
+            (Self::Vlc, TrackChoice::Auto) => {
+                languages("sub-language", &settings.subtitle_languages)
+            }
+            (Self::Mpv, TrackChoice::Off) => Some("--sid=no".to_string()),
+            (Self::Mpv, TrackChoice::Track(number)) => Some(format!("--sid={number}")),
+            (Self::Vlc, TrackChoice::Off) => Some("--no-spu".to_string()),
+            (Self::Vlc, TrackChoice::Track(number)) => Some(format!("--sub-track={}", number - 1)),
+        };
+
+        audio.into_iter().chain(subtitle).collect()
+    }
+}
+
+pub struct Player;
+
+impl Player {
+    /// Plays the request with the configured backend.
+    pub fn start(request: &PlayRequest) -> Result<Box<dyn Playing>, Box<dyn Error>> {
+        let settings = &Config::get().player;
+        match settings.backend {
+            Backend::Command | Backend::Mpv => Local.play(request),
+            Backend::Kodi => Kodi::new(&settings.kodi).play(request),
+            Backend::Vlc => VlcHttp::new(&settings.vlc).play(request),
+        }
+    }
+}
+
+/// The configured command, run on this machine.
+struct Local;
+
+impl PlayerBackend for Local {
+    /// Starts the command on the request's path. Its first file selects the
+    /// command, the subtitles and the tracks, which are only picked for mpv
+    /// and VLC.
+    fn play(&self, request: &PlayRequest) -> Result<Box<dyn Playing>, Box<dyn Error>> {
+        let settings = &Config::get().player;
+        let file = request.files.first().ok_or("Nothing to play")?;
+        let template = settings.template(file);
+        let flavor = template.first().and_then(|program| Flavor::of(program));
+        let values = [
+            ("path", Some(Self::location(request, flavor, template)?)),
+            ("start", request.start.map(|start| start.to_string())),
+            (
+                "sub",
+                Self::subtitles(file).map(|sub| sub.to_string_lossy().into_owned()),
+            ),
+        ];
+
+        let mut args = template.iter().filter_map(|arg| {
+            let mut arg = arg.clone();
+            for (name, value) in &values {
+                let placeholder = format!("{{{name}}}");
+                if arg.contains(&placeholder) {
+                    arg = arg.replace(&placeholder, value.as_ref()?);
+                }
+            }
+            Some(arg)
+        });
+        let program = args.next().ok_or("The player command is empty")?;
+
+        let mut command = Command::new(&program);
+        // Commands of other players, e.g. for some extensions, are only waited
+        // for, as they would not understand mpv's options
+        let socket = (settings.backend == Backend::Mpv && flavor == Some(Flavor::Mpv))
+            .then(|| std::env::temp_dir().join(format!("cineteca-{}.sock", std::process::id())));
+        if let Some(socket) = &socket {
+            // A socket left over by a previous playback would be connected to
+            // before mpv replaces it
+            fs::remove_file(socket).ok();
+            command.arg(format!("--input-ipc-server={}", socket.display()));
+            // mpv always supports resuming, even if the command does not ask
+            if let Some(start) = request
+                .start
+                .filter(|_| !template.iter().any(|arg| arg.contains("{start}")))
+            {
+                command.arg(format!("--start={start}"));
+            }
+        }
+
+        if let Some(flavor) = flavor {
+            command.args(flavor.track_args(request.tracks, settings));
+        }
+
+        let child = command
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Could not start {program}: {e}"))?;
+
+        let launcher = Path::new(&program)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .filter(|name| LAUNCHERS.contains(name))
+            .map(str::to_owned);
+
+        Ok(Box::new(Process {
+            child,
+            socket,
+            launcher,
+        }))
+    }
+}
+
+impl Local {
+    /// Path the player is given, discs being passed as a `dvd://` or
+    /// `bluray://` location that only mpv and VLC understand.
+    fn location(
+        request: &PlayRequest,
+        flavor: Option<Flavor>,
+        template: &[String],
+    ) -> Result<String, Box<dyn Error>> {
+        let path = request.path.to_string_lossy();
+        match (request.disc, flavor) {
+            (None, _) => Ok(path.into_owned()),
+            (Some(kind), Some(_)) => Ok(format!("{}://{path}", kind.scheme())),
+            (Some(_), None) => Err(format!(
+                "{} cannot play discs, set player.command to mpv or VLC",
+                template.first().map_or("The player", String::as_str)
+            )
+            .into()),
+        }
+    }
+
+    /// First subtitle file named after the movie, e.g. `Movie.eng.srt`.
+    fn subtitles(file: &Path) -> Option<PathBuf> {
+        let stem = file.file_stem()?.to_str()?;
+        let mut subtitles: Vec<PathBuf> = fs::read_dir(file.parent()?)
+            .ok()?
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| Self::is_subtitle_of(name, stem))
+            })
+            .collect();
+        subtitles.sort();
+        subtitles.into_iter().next()
+    }
+
+    /// Whether the file name is the stem followed by a subtitle extension,
+    /// with a language or other tag in between, e.g. `Alien.eng.srt`.
+    fn is_subtitle_of(name: &str, stem: &str) -> bool {
+        let Some((rest, extension)) = name.rsplit_once('.') else {
+            return false;
+        };
+        SUBTITLE_EXTENSIONS
+            .iter()
+            .any(|known| known.eq_ignore_ascii_case(extension))
+            && rest
+                .strip_prefix(stem)
+                .is_some_and(|tag| tag.is_empty() || tag.starts_with('.'))
+    }
+}
+
+/// A player started by `Local`, followed through mpv's IPC when `socket`
+/// is set.
+struct Process {
+    child: Child,
+    socket: Option<PathBuf>,
+    launcher: Option<String>,
+}
+
+impl Playing for Process {
+    fn wait(
+        mut self: Box<Self>,
+        progress: &mut dyn FnMut(u64),
+    ) -> Result<Option<Playback>, Box<dyn Error>> {
+        let Some(socket) = &self.socket else {
+            let status = self.child.wait()?;
+            if !status.success() {
+                return Err(format!("The player exited with {status}").into());
+            }
+            return Ok(None);
+        };
+
+        let followed = Mpv::follow(socket, progress);
+        self.child.wait().ok();
+        fs::remove_file(socket).ok();
+        followed.map(Some)
+    }
+
+    fn launcher(&self) -> Option<&str> {
+        self.launcher.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn remote() -> RemoteSettings {
+        RemoteSettings {
+            path_map: BTreeMap::from([
+                (PathBuf::from("/mnt/nas"), "smb://nas/".to_string()),
+                (
+                    PathBuf::from("/mnt/nas/movies"),
+                    "smb://nas/films".to_string(),
+                ),
+            ]),
+            ..RemoteSettings::default()
+        }
+    }
+
+    fn disc_request(disc: Option<DiscKind>) -> PlayRequest {
+        PlayRequest {
+            path: PathBuf::from("/movies/Alien"),
+            files: vec![PathBuf::from("/movies/Alien")],
+            start: None,
+            tracks: TrackChoices::default(),
+            disc,
+        }
+    }
+
+    #[test]
+    fn discs_are_only_given_to_players_that_understand_them() {
+        let mpv = ["/usr/bin/mpv".to_string(), "{path}".to_string()];
+        let request = disc_request(Some(DiscKind::Dvd));
+        assert_eq!(
+            Local::location(&request, Flavor::of(&mpv[0]), &mpv).unwrap(),
+            "dvd:///movies/Alien"
+        );
+
+        let launcher = ["xdg-open".to_string(), "{path}".to_string()];
+        let error = Local::location(&request, Flavor::of(&launcher[0]), &launcher).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "xdg-open cannot play discs, set player.command to mpv or VLC"
+        );
+        assert_eq!(
+            Local::location(&disc_request(None), None, &launcher).unwrap(),
+            "/movies/Alien"
+        );
+    }
+
+    #[test]
+    fn automatic_tracks_are_picked_by_the_player_from_the_languages() {
+        let settings = PlayerSettings {
+            audio_languages: vec!["spa".to_string(), "eng".to_string()],
+            subtitle_languages: vec!["eng".to_string()],
+            ..PlayerSettings::default()
+        };
+        let auto = TrackChoices::default();
+        assert_eq!(
+            Flavor::Mpv.track_args(auto, &settings),
+            ["--alang=spa,eng", "--slang=eng"]
+        );
+        assert_eq!(
+            Flavor::Vlc.track_args(auto, &settings),
+            ["--audio-language=spa,eng", "--sub-language=eng"]
+        );
+        assert!(
+            Flavor::Mpv
+                .track_args(auto, &PlayerSettings::default())
+                .is_empty()
+        );
+
+        let chosen = TrackChoices {
+            audio: TrackChoice::Track(2),
+            subtitle: TrackChoice::Off,
+        };
+        assert_eq!(
+            Flavor::Mpv.track_args(chosen, &settings),
+            ["--aid=2", "--sid=no"]
+        );
+        assert_eq!(
+            Flavor::Vlc.track_args(chosen, &settings),
+            ["--audio-track=1", "--no-spu"]
+        );
+    }
+
+    #[test]
+    fn subtitles_must_be_named_after_the_whole_stem() {
+        assert!(Local::is_subtitle_of("Alien.srt", "Alien"));
+        assert!(Local::is_subtitle_of("Alien.eng.srt", "Alien"));
+        assert!(Local::is_subtitle_of("Alien.eng.forced.ass", "Alien"));
+        assert!(!Local::is_subtitle_of("Alien Resurrection.srt", "Alien"));
+        assert!(!Local::is_subtitle_of("Aliens.eng.srt", "Alien"));
+        assert!(!Local::is_subtitle_of("Alien.nfo", "Alien"));
+        assert!(!Local::is_subtitle_of("Alien.mkv", "Alien"));
+    }
+
+    #[test]
+    fn subtitle_extensions_ignore_case() {
+        assert!(Local::is_subtitle_of("Alien.SRT", "Alien"));
+        assert!(Local::is_subtitle_of("Alien.eng.Srt", "Alien"));
+        assert!(!Local::is_subtitle_of("Alien.NFO", "Alien"));
+    }
+
+    #[test]
+    fn remote_path_uses_the_deepest_folder() {
+        assert_eq!(
+            remote().remote_path(Path::new("/mnt/nas/movies/Alien/Alien.mkv")),
+            "smb://nas/films/Alien/Alien.mkv"
+        );
+        assert_eq!(
+            remote().remote_path(Path::new("/mnt/nas/series/Show.mkv")),
+            "smb://nas/series/Show.mkv"
+        );
+    }
+
+    #[test]
+    fn remote_path_keeps_unmapped_paths() {
+        assert_eq!(
+            remote().remote_path(Path::new("/home/me/Alien.mkv")),
+            "/home/me/Alien.mkv"
+        );
+        // Only whole folder names match
+        assert_eq!(
+            remote().remote_path(Path::new("/mnt/nasty/Alien.mkv")),
+            "/mnt/nasty/Alien.mkv"
+        );
+    }
+}
@@ -0,0 +1,398 @@
+use std::{
+    env,
+    error::Error,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    fuzzy,
+    movie::Movie,
+    series::{Episode, Series},
+};
+
+/// Syntax reminder shown where queries are typed.
+pub const SYNTAX: &str = "watched unwatched inprogress broken movie series
+length<2h year>=1970 rating>=4 (< <= > >= =)
+tag:horror path:~/nas title:alien, or a bare word
+-term to negate, `or` between groups of terms";
+
+/// Library entry a query is matched against.
+#[derive(Clone, Copy)]
+pub enum Entry<'a> {
+    Movie(&'a Movie),
+    Series(&'a Series),
+}
+
+#[derive(Clone, Copy)]
+enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+}
+
+impl Comparison {
+    /// Splits the comparison off the start of `value`.
+    fn parse(value: &str) -> Option<(Self, &str)> {
+        [
+            ("<=", Self::LessOrEqual),
+            (">=", Self::GreaterOrEqual),
+            ("<", Self::Less),
+            (">", Self::Greater),
+            ("=", Self::Equal),
+        ]
+        .into_iter()
+        .find_map(|(symbol, comparison)| Some((comparison, value.strip_prefix(symbol)?)))
+    }
+
+    const fn holds(self, value: u64, bound: u64) -> bool {
+        match self {
+            Self::Less => value < bound,
+            Self::LessOrEqual => value <= bound,
+            Self::Greater => value > bound,
+            Self::GreaterOrEqual => value >= bound,
+            Self::Equal => value == bound,
+        }
+    }
+}
+
+#[derive(Clone)]
+enum Term {
+    Watched,
+    Unwatched,
+    InProgress,
+    Broken,
+    Movie,
+    Series,
+    /// Seconds, of a movie up to its credits or of the next episode
+    Length(Comparison, u64),
+    Year(Comparison, u64),
+    Rating(Comparison, u64),
+    Tag(String),
+    /// Folder the files are in, or part of their path if it is relative
+    Path(PathBuf),
+    Title(String),
+}
+
+impl Term {
+    fn parse(word: &str) -> Result<Self, Box<dyn Error>> {
+        if let Some(term) = match word.to_lowercase().as_str() {
+            "watched" => Some(Self::Watched),
+            "unwatched" => Some(Self::Unwatched),
+            "inprogress" | "in-progress" => Some(Self::InProgress),
+            "broken" => Some(Self::Broken),
+            "movie" | "movies" => Some(Self::Movie),
+            "series" => Some(Self::Series),
+            _ => None,
+        } {
+            return Ok(term);
+        }
+
+        if let Some((field, value)) = word.split_once(':') {
+            return match field {
+                "tag" => Ok(Self::Tag(value.to_lowercase())),
+                "path" => Ok(Self::Path(expand_home(value))),
+                "title" => Ok(Self::Title(value.to_owned())),
+                _ => Err(format!("Unknown field: {field}").into()),
+            };
+        }
+
+        let Some(split) = word.find(['<', '>', '=']) else {
+            return Ok(Self::Title(word.to_owned()));
+        };
+        let (field, rest) = word.split_at(split);
+        let (comparison, value) =
+            Comparison::parse(rest).ok_or_else(|| format!("Invalid comparison: {word}"))?;
+        let number = || {
+            value
+                .parse()
+                .map_err(|_| format!("Invalid number in {word}"))
+        };
+        match field {
+            "length" => Ok(Self::Length(comparison, parse_length(value)?)),
+            "year" => Ok(Self::Year(comparison, number()?)),
+            "rating" => Ok(Self::Rating(comparison, number()?)),
+            _ => Err(format!("Unknown field: {field}").into()),
+        }
+    }
+
+    fn matches(&self, entry: Entry) -> bool {
+        let compare = |value: Option<u64>, comparison: Comparison, bound: u64| {
+            value.is_some_and(|value| comparison.holds(value, bound))
+        };
+        match (self, entry) {
+            (Self::Watched, _) => entry.since_watched(),
+            (Self::Unwatched, _) => !entry.since_watched(),
+            (Self::InProgress, Entry::Movie(movie)) => movie.is_in_progress(),
+            (Self::InProgress, Entry::Series(series)) => series.is_in_progress(),
+            (Self::Broken, Entry::Movie(movie)) => movie.is_broken(),
+            (Self::Movie, Entry::Movie(_)) | (Self::Series, Entry::Series(_)) => true,
+            (Self::Length(comparison, bound), _) => compare(entry.length(), *comparison, *bound),
+            (Self::Year(comparison, bound), Entry::Movie(movie)) => {
+                compare(movie.year().map(u64::from), *comparison, *bound)
+            }
+            (Self::Rating(comparison, bound), Entry::Movie(movie)) => {
+                compare(movie.rating().map(u64::from), *comparison, *bound)
+            }
+            (Self::Tag(tag), Entry::Movie(movie)) => movie.has_tag(tag),
+            (Self::Path(path), _) => entry.paths().iter().any(|file| {
+                if path.is_absolute() {
+                    file.starts_with(path)
+                } else {
+                    file.to_string_lossy().contains(&*path.to_string_lossy())
+                }
+            }),
+            (Self::Title(title), _) => fuzzy::find(title, entry.name()).is_some(),
+            _ => false,
+        }
+    }
+}
+
+impl Entry<'_> {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Movie(movie) => movie.name(),
+            Self::Series(series) => series.name(),
+        }
+    }
+
+    fn since_watched(&self) -> bool {
+        match self {
+            Self::Movie(movie) => movie.since_watched().is_some(),
+            Self::Series(series) => series.since_watched().is_some(),
+        }
+    }
+
+    fn length(&self) -> Option<u64> {
+        match self {
+            Self::Movie(movie) => Some(movie.effective_length()),
+            Self::Series(series) => series.next_episode().map(Episode::length),
+        }
+    }
+
+    fn paths(&self) -> Vec<&Path> {
+        match self {
+            Self::Movie(movie) => movie.paths().map(PathBuf::as_path).collect(),
+            Self::Series(series) => series.episodes().map(Episode::path).collect(),
+        }
+    }
+}
+
+/// Entries matching every term of at least one group, terms being negated
+/// when they start with `-`, e.g. `unwatched length<2h or tag:horror -year<1980`.
+#[derive(Clone)]
+pub struct Query {
+    text: String,
+    groups: Vec<Vec<(bool, Term)>>,
+}
+
+impl Display for Query {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+impl Query {
+    pub fn parse(text: &str) -> Result<Self, Box<dyn Error>> {
+        let mut groups = vec![Vec::new()];
+        for word in text.split_whitespace() {
+            if word.eq_ignore_ascii_case("or") {
+                groups.push(Vec::new());
+                continue;
+            }
+            let (negated, word) = word
+                .strip_prefix('-')
+                .map_or((false, word), |word| (true, word));
+            let group = groups.last_mut().ok_or("Empty query")?;
+            group.push((negated, Term::parse(word)?));
+        }
+
+        match groups.as_slice() {
+            [group] if group.is_empty() => return Err("Empty query".into()),
+            groups if groups.iter().any(Vec::is_empty) => {
+                return Err("`or` needs terms on both sides".into());
+            }
+            _ => {}
+        }
+        Ok(Self {
+            text: text.split_whitespace().collect::<Vec<_>>().join(" "),
+            groups,
+        })
+    }
+
+    pub fn matches(&self, entry: Entry) -> bool {
+        self.groups.iter().any(|group| {
+            group
+                .iter()
+                .all(|(negated, term)| term.matches(entry) != *negated)
+        })
+    }
+}
+
+/// Parses a length such as `2h`, `90m`, `1h30m` or `90` (minutes) into
+/// seconds.
+fn parse_length(value: &str) -> Result<u64, Box<dyn Error>> {
+    let invalid = || format!("Invalid length: {value}");
+    if let Ok(minutes) = value.parse::<u64>() {
+        return Ok(minutes.checked_mul(60).ok_or_else(invalid)?);
+    }
+
+    let mut seconds: u64 = 0;
+    let mut number = String::new();
+    for c in value.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return Err(invalid().into()),
+        };
+        seconds = number
+            .parse::<u64>()
+            .ok()
+            .and_then(|number| number.checked_mul(unit))
+            .and_then(|part| seconds.checked_add(part))
+            .ok_or_else(invalid)?;
+        number.clear();
+    }
+
+    if number.is_empty() && !value.is_empty() {
+        Ok(seconds)
+    } else {
+        Err(invalid().into())
+    }
+}
+
+/// Replaces a leading `~` with the home directory.
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest.trim_start_matches('/')),
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use super::*;
+
+    fn movie(fields: &Value) -> Movie {
+        let mut movie = json!({ "length": 7020, "since_watched": null });
+        for (name, value) in fields.as_object().unwrap() {
+            movie[name] = value.clone();
+        }
+        serde_json::from_value(movie).unwrap()
+    }
+
+    fn alien() -> Movie {
+        movie(&json!({
+            "name": "Alien (1979).mkv",
+            "path": "/mnt/nas/movies/Alien (1979).mkv",
+            "rating": 5,
+            "tags": ["horror"],
+        }))
+    }
+
+    fn up() -> Movie {
+        movie(&json!({
+            "name": "Up (2009).mkv",
+            "path": "/srv/films/Up (2009).mkv",
+            "length": 5760,
+            "since_watched": { "secs_since_epoch": 1_700_000_000, "nanos_since_epoch": 0 },
+        }))
+    }
+
+    fn matching(query: &str) -> Vec<String> {
+        let query = Query::parse(query).unwrap();
+        [alien(), up()]
+            .iter()
+            .filter(|movie| query.matches(Entry::Movie(movie)))
+            .map(|movie| movie.name().to_owned())
+            .collect()
+    }
+
+    #[test]
+    fn lengths_are_read_in_minutes_or_units() {
+        assert_eq!(parse_length("90").unwrap(), 5400);
+        assert_eq!(parse_length("2h").unwrap(), 7200);
+        assert_eq!(parse_length("1h30m").unwrap(), 5400);
+        assert_eq!(parse_length("45s").unwrap(), 45);
+    }
+
+    #[test]
+    fn bad_lengths_are_refused() {
+        for value in [
+            "",
+            "h",
+            "2x",
+            "1h30",
+            "18446744073709551615",
+            "99999999999999999999h",
+        ] {
+            assert_eq!(
+                parse_length(value).map_err(|e| e.to_string()),
+                Err(format!("Invalid length: {value}"))
+            );
+        }
+        assert!(parse_length("5124095576030431h1h").is_err());
+    }
+
+    #[test]
+    fn terms_of_a_group_must_all_match() {
+        assert_eq!(matching("unwatched tag:horror"), ["Alien (1979).mkv"]);
+        assert_eq!(matching("length<100m"), ["Up (2009).mkv"]);
+        assert_eq!(matching("year>=1970 rating>=4"), ["Alien (1979).mkv"]);
+        assert_eq!(matching("watched year<2000"), Vec::<String>::new());
+        assert_eq!(matching("title:alien"), ["Alien (1979).mkv"]);
+        assert_eq!(matching("up"), ["Up (2009).mkv"]);
+    }
+
+    #[test]
+    fn or_and_negation_combine_groups() {
+        assert_eq!(
+            matching("tag:horror or length<=96m"),
+            ["Alien (1979).mkv", "Up (2009).mkv"]
+        );
+        assert_eq!(matching("-tag:horror"), ["Up (2009).mkv"]);
+        assert_eq!(
+            matching("movie -watched or year=2009"),
+            ["Alien (1979).mkv", "Up (2009).mkv"]
+        );
+        assert_eq!(matching("series"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn paths_are_folders_or_parts_of_a_path() {
+        assert_eq!(matching("path:/mnt/nas"), ["Alien (1979).mkv"]);
+        assert_eq!(matching("path:/mnt/na"), Vec::<String>::new());
+        assert_eq!(matching("path:films"), ["Up (2009).mkv"]);
+
+        let Some(home) = env::var_os("HOME") else {
+            return;
+        };
+        let Term::Path(path) = Term::parse("path:~/movies").unwrap() else {
+            panic!("path: is not read as a path");
+        };
+        assert_eq!(path, PathBuf::from(home).join("movies"));
+    }
+
+    #[test]
+    fn bad_queries_are_refused() {
+        let error = |query| Query::parse(query).err().map(|e| e.to_string());
+        assert_eq!(error("genre:horror"), Some("Unknown field: genre".into()));
+        assert_eq!(error("size>2"), Some("Unknown field: size".into()));
+        assert_eq!(error("year>abc"), Some("Invalid number in year>abc".into()));
+        assert_eq!(error("length<2d"), Some("Invalid length: 2d".into()));
+        assert_eq!(error("   "), Some("Empty query".into()));
+        assert_eq!(
+            error("watched or"),
+            Some("`or` needs terms on both sides".into())
+        );
+    }
+}
@@ -0,0 +1,393 @@
+use std::{collections::BTreeMap, error::Error, ops::RangeInclusive, path::Path, time::SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    movie::{self, MovieLength, MoviePath},
+    session::Session,
+};
+
+const SEPARATORS: [char; 7] = [' ', '.', '_', '-', '(', '[', ']'];
+
+#[derive(Serialize, Deserialize, Eq, PartialEq, Clone, Hash)]
+pub struct Episode {
+    /// Name of the series the file belongs to, only needed while grouping
+    #[serde(skip)]
+    series: String,
+    season: u32,
+    number: u32,
+    title: String,
+    path: MoviePath,
+    length: u64,
+    since_watched: Option<SystemTime>,
+    /// Seconds into the episode at which the last playback was stopped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    resume_position: Option<u64>,
+    /// When the resume position was last saved or cleared, to tell whether a
+    /// position saved by mpv is newer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    resume_saved: Option<SystemTime>,
+    /// Every time the episode was played, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    sessions: Vec<Session>,
+}
+
+impl Episode {
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    /// Short episode code, e.g. `S01E02`.
+    pub fn code(&self) -> String {
+        format!("S{:02}E{:02}", self.season, self.number)
+    }
+
+    pub fn label(&self) -> String {
+        match self.title.as_str() {
+            "" => self.code(),
+            title => format!("{} {title}", self.code()),
+        }
+    }
+
+    pub const fn since_watched(&self) -> Option<SystemTime> {
+        self.since_watched
+    }
+
+    pub const fn length(&self) -> u64 {
+        self.length
+    }
+
+    pub const fn resume_position(&self) -> Option<u64> {
+        self.resume_position
+    }
+
+    /// Seconds left to watch, from where the last playback was stopped.
+    pub fn remaining_length(&self) -> u64 {
+        self.length
+            .saturating_sub(self.resume_position.unwrap_or_default())
+    }
+
+    pub const fn resume_saved(&self) -> Option<SystemTime> {
+        self.resume_saved
+    }
+
+    pub fn set_resume_position(&mut self, resume_position: Option<u64>) {
+        self.resume_position = resume_position;
+        self.resume_saved = Some(SystemTime::now());
+    }
+
+    pub fn sessions(&self) -> &[Session] {
+        &self.sessions
+    }
+
+    pub fn add_session(&mut self, session: Session) {
+        self.sessions.push(session);
+    }
+
+    pub fn pretty_length(&self) -> String {
+        movie::pretty_length(self.length)
+    }
+
+    pub fn pretty_since_watched(&self) -> String {
+        movie::pretty_since_watched(self.since_watched)
+    }
+
+    pub fn toggle_watched(&mut self) {
+        match self.since_watched {
+            Some(_) => self.since_watched = None,
+            None => self.set_watched(),
+        }
+    }
+
+    /// Marks the episode as watched now, forgetting where it was stopped.
+    pub fn set_watched(&mut self) {
+        self.since_watched = Some(SystemTime::now());
+        self.resume_position = None;
+    }
+
+    /// Finds an episode marker such as `S01E02` or `1x02` in a file stem,
+    /// returning its byte range along with the season and episode numbers.
+    fn find_marker(stem: &str) -> Option<(usize, usize, u32, u32)> {
+        let bytes = stem.as_bytes();
+
+        let digits = |from: usize, lengths: RangeInclusive<usize>| -> Option<(u32, usize)> {
+            let len = bytes
+                .get(from..)?
+                .iter()
+                .take_while(|b| b.is_ascii_digit())
+                .count();
+            if !lengths.contains(&len) {
+                return None;
+            }
+            Some((stem[from..from + len].parse().ok()?, from + len))
+        };
+
+        (0..bytes.len())
+            .filter(|&i| i == 0 || !bytes[i - 1].is_ascii_alphanumeric())
+            .find_map(|start| {
+                let (season, episode_start, lengths) = if bytes[start].eq_ignore_ascii_case(&b's') {
+                    let (season, end) = digits(start + 1, 1..=2)?;
+                    bytes
+                        .get(end)
+                        .filter(|b| b.eq_ignore_ascii_case(&b'e'))
+                        .map(|_| (season, end + 1, 1..=3))?
+                } else {
+                    let (season, end) = digits(start, 1..=2)?;
+                    // A single digit, as in "2x4", is more likely part of a title
+                    bytes
+                        .get(end)
+                        .filter(|b| b.eq_ignore_ascii_case(&b'x'))
+                        .map(|_| (season, end + 1, 2..=3))?
+                };
+                let (number, end) = digits(episode_start, lengths)?;
+
+                // Reject things like "1x2160p" or "S01E02x"
+                let is_word_end = bytes.get(end).is_none_or(|b| !b.is_ascii_alphanumeric())
+                    || bytes[end].eq_ignore_ascii_case(&b'e');
+                is_word_end.then_some((start, end, season, number))
+            })
+    }
+
+    /// Series name for files without one in the file name, taken from the
+    /// folder (skipping season folders such as `Season 1`).
+    fn folder_name(path: &Path) -> Option<String> {
+        path.ancestors()
+            .skip(1)
+            .filter_map(|dir| dir.file_name())
+            .map(|name| name.to_string_lossy())
+            .find(|name| {
+                let lowercase = name.to_lowercase();
+                let is_season_number = lowercase
+                    .strip_prefix('s')
+                    .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+                !(lowercase.starts_with("season") || lowercase == "specials" || is_season_number)
+            })
+            .map(|name| clean(&name))
+    }
+}
+
+impl TryFrom<&Path> for Episode {
+    type Error = Box<dyn Error>;
+
+    fn try_from(path: &Path) -> Result<Self, Self::Error> {
+        let path = MoviePath::try_from(path)?;
+
+        let stem = path
+            .as_path()
+            .file_stem()
+            .ok_or("Path does not have a valid filename")?
+            .to_string_lossy()
+            .into_owned();
+
+        let (start, end, season, number) =
+            Self::find_marker(&stem).ok_or("File name does not contain an episode marker")?;
+
+        let series = Some(clean(&stem[..start]))
+            .filter(|name| !name.is_empty())
+            .or_else(|| Self::folder_name(path.as_path()))
+            .ok_or("Could not determine the series name")?;
+
+        Ok(Self {
+            series,
+            season,
+            number,
+            title: clean(&stem[end..]),
+            length: MovieLength::probe(path.as_path())?,
+            path,
+            since_watched: None,
+            resume_position: None,
+            resume_saved: None,
+            sessions: Vec::new(),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Eq, PartialEq, Clone, Hash)]
+pub struct Season {
+    number: u32,
+    episodes: Vec<Episode>,
+}
+
+#[derive(Serialize, Deserialize, Eq, PartialEq, Clone, Hash)]
+pub struct Series {
+    name: String,
+    seasons: Vec<Season>,
+}
+
+impl Series {
+    /// Groups loose episodes into series and seasons, sorted by name.
+    pub fn group(episodes: Vec<Episode>) -> Vec<Self> {
+        let mut series: BTreeMap<String, (String, BTreeMap<u32, Vec<Episode>>)> = BTreeMap::new();
+
+        for episode in episodes {
+            series
+                .entry(episode.series.to_lowercase())
+                .or_insert_with(|| (episode.series.clone(), BTreeMap::new()))
+                .1
+                .entry(episode.season)
+                .or_default()
+                .push(episode);
+        }
+
+        series
+            .into_values()
+            .map(|(name, seasons)| Self {
+                name,
+                seasons: seasons
+                    .into_iter()
+                    .map(|(number, mut episodes)| {
+                        episodes.sort_by_key(|episode| episode.number);
+                        Season { number, episodes }
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn seasons(&self) -> &[Season] {
+        &self.seasons
+    }
+
+    pub fn episodes(&self) -> impl Iterator<Item = &Episode> {
+        self.seasons.iter().flat_map(|season| &season.episodes)
+    }
+
+    pub fn episodes_mut(&mut self) -> impl Iterator<Item = &mut Episode> {
+        self.seasons
+            .iter_mut()
+            .flat_map(|season| &mut season.episodes)
+    }
+
+    pub fn episode_mut(&mut self, path: &Path) -> Option<&mut Episode> {
+        self.seasons
+            .iter_mut()
+            .flat_map(|season| &mut season.episodes)
+            .find(|episode| episode.path() == path)
+    }
+
+    pub fn num_episodes(&self) -> usize {
+        self.episodes().count()
+    }
+
+    pub fn num_watched(&self) -> usize {
+        self.episodes()
+            .filter(|episode| episode.since_watched.is_some())
+            .count()
+    }
+
+    /// When the last episode was watched, only once every episode has been.
+    pub fn since_watched(&self) -> Option<SystemTime> {
+        self.episodes()
+            .map(|episode| episode.since_watched)
+            .collect::<Option<Vec<_>>>()
+            .and_then(|times| times.into_iter().max())
+    }
+
+    /// Whether the series was started but not finished yet.
+    pub fn is_in_progress(&self) -> bool {
+        self.since_watched().is_none()
+            && self
+                .episodes()
+                .any(|episode| episode.since_watched.is_some() || episode.resume_position.is_some())
+    }
+
+    /// The episode after the most recently watched one, or the first one not
+    /// yet watched if that was the last episode.
+    pub fn next_episode(&self) -> Option<&Episode> {
+        let episodes: Vec<_> = self.episodes().collect();
+
+        let after_last = episodes
+            .iter()
+            .enumerate()
+            .filter_map(|(i, episode)| Some((episode.since_watched?, i)))
+            .max()
+            .and_then(|(_, i)| episodes.get(i + 1))
+            .filter(|episode| episode.since_watched.is_none());
+
+        after_last
+            .or_else(|| {
+                episodes
+                    .iter()
+                    .find(|episode| episode.since_watched.is_none())
+            })
+            .copied()
+    }
+
+    /// Carries the watched state of episodes over from a previous scan.
+    pub fn restore_watched(&mut self, old: &[Self]) {
+        let old_episodes = || old.iter().flat_map(Self::episodes);
+
+        for episode in self.seasons.iter_mut().flat_map(|s| &mut s.episodes) {
+            let old = old_episodes().find(|old| old.path == episode.path);
+            episode.since_watched = old.and_then(|old| old.since_watched);
+            episode.resume_position = old.and_then(|old| old.resume_position);
+            episode.resume_saved = old.and_then(|old| old.resume_saved);
+            episode.sessions = old.map(|old| old.sessions.clone()).unwrap_or_default();
+        }
+    }
+}
+
+/// Turns a file name fragment like `.Breaking.Bad.` into `Breaking Bad`.
+fn clean(name: &str) -> String {
+    name.replace(['.', '_'], " ")
+        .trim_matches(SEPARATORS)
+        .to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_marker_reads_season_and_episode() {
+        let cases = [
+            ("Show.S01E02.Pilot", (5, 11, 1, 2)),
+            ("show s1e2", (5, 9, 1, 2)),
+            ("Show.S02E103", (5, 12, 2, 103)),
+            ("S01E02E03", (0, 6, 1, 2)),
+            ("Show 1x02 Pilot", (5, 9, 1, 2)),
+            ("Show_12X05", (5, 10, 12, 5)),
+            ("1x02", (0, 4, 1, 2)),
+        ];
+        for (stem, marker) in cases {
+            assert_eq!(Episode::find_marker(stem), Some(marker), "{stem}");
+        }
+    }
+
+    #[test]
+    fn find_marker_ignores_lookalikes() {
+        let stems = [
+            "Movie.1920x1080",
+            "Movie.1x2160p",
+            "The 2x4 Builders",
+            "Boss01E02",
+            "Show.S01E02x",
+            "Show.S001E02",
+            "Mission S1",
+        ];
+        for stem in stems {
+            assert_eq!(Episode::find_marker(stem), None, "{stem}");
+        }
+    }
+
+    #[test]
+    fn folder_name_skips_season_folders() {
+        let cases = [
+            ("/tv/The_Wire/S01E02.mkv", "The Wire"),
+            ("/tv/The Wire/Season 1/S01E02.mkv", "The Wire"),
+            ("/tv/Show/s2/2x05.mkv", "Show"),
+            ("/tv/Show/Specials/S00E01.mkv", "Show"),
+        ];
+        for (path, name) in cases {
+            assert_eq!(
+                Episode::folder_name(Path::new(path)).as_deref(),
+                Some(name),
+                "{path}"
+            );
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+/// A run of the player on a movie or episode, from its start to its exit.
+#[derive(Serialize, Deserialize, Eq, PartialEq, Clone, Copy, Hash)]
+pub struct Session {
+    start: SystemTime,
+    end: SystemTime,
+}
+
+impl Session {
+    pub const fn new(start: SystemTime, end: SystemTime) -> Self {
+        Self { start, end }
+    }
+
+    pub const fn start(&self) -> SystemTime {
+        self.start
+    }
+
+    /// Wall-clock seconds the player was open.
+    pub fn duration(&self) -> u64 {
+        self.end
+            .duration_since(self.start)
+            .map_or(0, |duration| duration.as_secs())
+    }
+}
+
+/// Number of sessions and the time spent in them, e.g. `3 (4h 12m)`.
+pub fn pretty_sessions(sessions: &[Session]) -> String {
+    let total = sessions.iter().map(Session::duration).sum();
+    format!(
+        "{} ({})",
+        sessions.len(),
+        crate::movie::pretty_length(total)
+    )
+}
@@ -0,0 +1,173 @@
+use std::{cmp::Ordering, fmt::Display};
+
+use serde::{Deserialize, Serialize};
+
+/// What the list is sorted by.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    /// Not watched alphabetically, then watched, most recent first
+    #[default]
+    Unwatched,
+    Title,
+    Length,
+    LastWatched,
+    Added,
+    Size,
+    Resolution,
+    Year,
+    Rating,
+}
+
+impl Display for SortKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::Unwatched => write!(f, "Not watched first"),
+            Self::Title => write!(f, "Title"),
+            Self::Length => write!(f, "Length"),
+            Self::LastWatched => write!(f, "Last watched"),
+            Self::Added => write!(f, "Date added"),
+            Self::Size => write!(f, "Size"),
+            Self::Resolution => write!(f, "Resolution"),
+            Self::Year => write!(f, "Year"),
+            Self::Rating => write!(f, "Rating"),
+        }
+    }
+}
+
+impl SortKey {
+    const fn next(self) -> Self {
+        match self {
+            Self::Unwatched => Self::Title,
+            Self::Title => Self::Length,
+            Self::Length => Self::LastWatched,
+            Self::LastWatched => Self::Added,
+            Self::Added => Self::Size,
+            Self::Size => Self::Resolution,
+            Self::Resolution => Self::Year,
+            Self::Year => Self::Rating,
+            Self::Rating => Self::Unwatched,
+        }
+    }
+}
+
+/// Sort key and direction of the list, remembered in the library file.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Default)]
+#[serde(default)]
+pub struct SortOrder {
+    pub key: SortKey,
+    pub descending: bool,
+}
+
+impl Display for SortOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let arrow = if self.descending { "↓" } else { "↑" };
+        write!(f, "{} {arrow}", self.key)
+    }
+}
+
+impl SortOrder {
+    pub const fn cycle_key(&mut self) {
+        self.key = self.key.next();
+    }
+
+    pub const fn toggle_direction(&mut self) {
+        self.descending = !self.descending;
+    }
+
+    /// Sorts by a key, ascending, or switches the direction if the list is
+    /// already sorted by it.
+    pub fn sort_by(&mut self, key: SortKey) {
+        if self.key == key {
+            self.toggle_direction();
+        } else {
+            *self = Self {
+                key,
+                descending: false,
+            };
+        }
+    }
+
+    /// Orders two values in the chosen direction. Missing values always go
+    /// last.
+    pub fn compare<T: Ord>(self, a: Option<T>, b: Option<T>) -> Ordering {
+        match (a, b) {
+            (Some(a), Some(b)) if self.descending => b.cmp(&a),
+            (Some(a), Some(b)) => a.cmp(&b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+    }
+}
+
+/// Compares titles ignoring case, with runs of digits compared by their
+/// value so that "Part 2" comes before "Part 10".
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let number = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+                    let mut digits = String::new();
+                    while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                        digits.push(digit);
+                    }
+                    digits.trim_start_matches('0').to_owned()
+                };
+                let (x, y) = (number(&mut a), number(&mut b));
+                // Without leading zeros, a longer number is a bigger one
+                let ordering = x.len().cmp(&y.len()).then_with(|| x.cmp(&y));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                let ordering = x.to_lowercase().cmp(y.to_lowercase());
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_are_compared_by_value() {
+        assert_eq!(natural_cmp("Part 2", "Part 10"), Ordering::Less);
+        assert_eq!(natural_cmp("Part 10", "Part 9"), Ordering::Greater);
+        assert_eq!(natural_cmp("Alien 3", "Alien 3"), Ordering::Equal);
+        assert_eq!(natural_cmp("2001", "Part 1"), Ordering::Less);
+    }
+
+    #[test]
+    fn leading_zeros_are_ignored() {
+        assert_eq!(natural_cmp("Episode 007", "Episode 7"), Ordering::Equal);
+        assert_eq!(natural_cmp("Episode 02", "Episode 10"), Ordering::Less);
+        assert_eq!(natural_cmp("Episode 010", "Episode 9"), Ordering::Greater);
+    }
+
+    #[test]
+    fn case_is_ignored() {
+        assert_eq!(natural_cmp("alien", "Alien"), Ordering::Equal);
+        assert_eq!(natural_cmp("alien", "Blade Runner"), Ordering::Less);
+        assert_eq!(natural_cmp("ALIENS", "alien"), Ordering::Greater);
+    }
+
+    #[test]
+    fn a_prefix_comes_first() {
+        assert_eq!(natural_cmp("Alien", "Aliens"), Ordering::Less);
+        assert_eq!(natural_cmp("", "Alien"), Ordering::Less);
+    }
+}
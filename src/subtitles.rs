@@ -0,0 +1,152 @@
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use ffmpeg_next::{Packet, codec, encoder, format, media};
+
+use crate::tracks::Track;
+
+pub struct Subtitles;
+
+impl Subtitles {
+    /// Subtitle streams of the file in a text format that can be written to
+    /// a sidecar file as is.
+    pub fn tracks(path: &Path) -> Result<Vec<Track>, Box<dyn Error>> {
+        let input = format::input(&path)?;
+
+        Ok(input
+            .streams()
+            .filter(|stream| stream.parameters().medium() == media::Type::Subtitle)
+            .enumerate()
+            .filter(|(_, stream)| {
+                matches!(
+                    stream.parameters().id(),
+                    codec::Id::SUBRIP
+                        | codec::Id::SRT
+                        | codec::Id::ASS
+                        | codec::Id::SSA
+                        | codec::Id::WEBVTT
+                )
+            })
+            .map(|(i, stream)| Track::new(&stream, i + 1))
+            .collect())
+    }
+
+    /// Copies the tracks to files named `<movie>.<language>.<ext>` next to
+    /// the movie, returning the files written. Existing files are left
+    /// untouched.
+    pub fn extract(path: &Path, tracks: &[Track]) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        let mut written = Vec::new();
+        if let Err(e) = Self::write_sidecars(path, tracks, &mut written) {
+            // Partial files would be mistaken for complete ones next time
+            for sidecar in &written {
+                fs::remove_file(sidecar).ok();
+            }
+            return Err(e);
+        }
+        Ok(written)
+    }
+
+    /// Writes the sidecar files, each one being added to `written` as soon
+    /// as it is created.
+    fn write_sidecars(
+        path: &Path,
+        tracks: &[Track],
+        written: &mut Vec<PathBuf>,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut input = format::input(&path)?;
+        let mut outputs = Vec::new();
+
+        for track in tracks {
+            let sidecar = Self::sidecar_path(path, track, tracks)?;
+            if sidecar.exists() {
+                continue;
+            }
+
+            let stream = input
+                .stream(track.index())
+                .ok_or("Subtitle stream not found")?;
+            written.push(sidecar.clone());
+            let mut output = format::output(&sidecar)?;
+            output
+                .add_stream(encoder::find(codec::Id::None))?
+                .set_parameters(stream.parameters());
+            output.write_header()?;
+
+            outputs.push((track.index(), stream.time_base(), output));
+        }
+
+        // Subtitle packets are spread through the whole file
+        let mut packet = Packet::empty();
+        loop {
+            match packet.read(&mut input) {
+                Ok(()) => {}
+                Err(ffmpeg_next::Error::Eof) => break,
+                Err(e) => return Err(e.into()),
+            }
+
+            let Some((_, time_base, output)) = outputs
+                .iter_mut()
+                .find(|(index, ..)| *index == packet.stream())
+            else {
+                continue;
+            };
+            let output_time_base = output
+                .stream(0)
+                .ok_or("Sidecar stream not found")?
+                .time_base();
+            packet.rescale_ts(*time_base, output_time_base);
+            packet.set_position(-1);
+            packet.set_stream(0);
+            packet.write_interleaved(output)?;
+        }
+
+        for (_, _, output) in &mut outputs {
+            output.write_trailer()?;
+        }
+        Ok(())
+    }
+
+    /// The stream index is added when several of the tracks share their
+    /// language and format.
+    fn sidecar_path(
+        path: &Path,
+        track: &Track,
+        tracks: &[Track],
+    ) -> Result<PathBuf, Box<dyn Error>> {
+        let stem = path
+            .file_stem()
+            .ok_or("Path does not have a valid filename")?
+            .to_string_lossy();
+        let is_ambiguous = tracks.iter().any(|other| {
+            other.index() != track.index()
+                && other.language() == track.language()
+                && extension(other) == extension(track)
+        });
+
+        let name = if is_ambiguous {
+            format!(
+                "{stem}.{}.{}.{}",
+                track.language(),
+                track.index(),
+                extension(track)
+            )
+        } else {
+            format!("{stem}.{}.{}", track.language(), extension(track))
+        };
+
+        Ok(path.with_file_name(name))
+    }
+}
+
+/// Extension of the sidecar file of a text subtitle track, which also
+/// selects the muxer.
+const fn extension(track: &Track) -> &'static str {
+    match track.codec() {
+        codec::Id::ASS | codec::Id::SSA => "ass",
+        codec::Id::WEBVTT => "vtt",
+        _ => "srt",
+    }
+}
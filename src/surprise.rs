@@ -0,0 +1,183 @@
+use std::{
+    process,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::movie::Movie;
+
+const YEAR: u64 = 365 * 24 * 3600;
+
+/// Constraints of a random pick, the defaults being read from the config.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct SurpriseSettings {
+    /// Longest movie that can be picked, in minutes
+    pub max_length: Option<u64>,
+    /// Tags a movie must all have to be picked
+    pub tags: Vec<String>,
+    /// Allows watched movies, unless watched in the last given number of
+    /// years. Without it, only unwatched movies are picked
+    pub not_watched_years: Option<u64>,
+    /// Makes movies added to the library long ago more likely to be picked
+    pub favor_older: bool,
+    /// Number of recent picks that are not picked again
+    pub recent_picks: usize,
+}
+
+impl Default for SurpriseSettings {
+    fn default() -> Self {
+        Self {
+            max_length: None,
+            tags: Vec::new(),
+            not_watched_years: None,
+            favor_older: false,
+            recent_picks: 10,
+        }
+    }
+}
+
+impl SurpriseSettings {
+    /// Whether a movie meets the constraints, recent picks aside.
+    pub fn allows(&self, movie: &Movie) -> bool {
+        let short_enough = self
+            .max_length
+            .is_none_or(|minutes| movie.length() <= minutes * 60);
+        let tagged = self.tags.iter().all(|tag| movie.has_tag(tag));
+        let not_recently_watched = match (self.not_watched_years, movie.since_watched()) {
+            (Some(years), Some(watched)) => SystemTime::now()
+                .duration_since(watched)
+                .is_ok_and(|since| since >= Duration::from_secs(years.saturating_mul(YEAR))),
+            (None, Some(_)) => false,
+            (_, None) => true,
+        };
+
+        !movie.is_broken() && short_enough && tagged && not_recently_watched
+    }
+}
+
+/// Xorshift generator, seeded from the clock. Good enough to pick a movie.
+struct Rng(u64);
+
+impl Rng {
+    fn new() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos());
+        let seed = u64::try_from(nanos % u128::from(u64::MAX)).unwrap_or_default()
+            ^ u64::from(process::id()).rotate_left(32);
+        // Xorshift gets stuck on zero
+        Self(seed.max(1))
+    }
+
+    const fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+pub struct Surprise;
+
+impl Surprise {
+    /// Picks a random movie meeting the constraints, avoiding the recent
+    /// picks unless nothing else is left.
+    pub fn pick<'a>(
+        movies: impl IntoIterator<Item = &'a Movie>,
+        settings: &SurpriseSettings,
+        recent: &[String],
+    ) -> Option<&'a Movie> {
+        let allowed: Vec<&Movie> = movies
+            .into_iter()
+            .filter(|movie| settings.allows(movie))
+            .collect();
+        let fresh: Vec<&Movie> = allowed
+            .iter()
+            .copied()
+            .filter(|movie| !recent.iter().any(|name| name == movie.name()))
+            .collect();
+        let mut candidates = if fresh.is_empty() { allowed } else { fresh };
+
+        // With favor_older, the oldest addition is n times as likely to be
+        // picked as the newest one
+        let weights: Vec<u64> = if settings.favor_older {
+            candidates.sort_by_key(|movie| movie.added());
+            (1..=candidates.len() as u64).rev().collect()
+        } else {
+            vec![1; candidates.len()]
+        };
+
+        let total: u64 = weights.iter().sum();
+        if total == 0 {
+            return None;
+        }
+        let mut target = Rng::new().next() % total;
+        candidates
+            .into_iter()
+            .zip(weights)
+            .find(|&(_, weight)| {
+                let found = target < weight;
+                target = target.saturating_sub(weight);
+                found
+            })
+            .map(|(movie, _)| movie)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn movie(name: &str, years_ago: Option<u64>) -> Movie {
+        let watched = years_ago.map(|years| {
+            let secs = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+            json!({ "secs_since_epoch": secs - years * YEAR, "nanos_since_epoch": 0 })
+        });
+        serde_json::from_value(json!({
+            "name": name,
+            "path": format!("/movies/{name}.mkv"),
+            "length": 6000,
+            "since_watched": watched,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn only_unwatched_movies_are_allowed_by_default() {
+        let settings = SurpriseSettings::default();
+
+        assert!(settings.allows(&movie("Alien", None)));
+        assert!(!settings.allows(&movie("Up", Some(10))));
+    }
+
+    #[test]
+    fn watched_movies_are_allowed_after_some_years() {
+        let settings = SurpriseSettings {
+            not_watched_years: Some(5),
+            ..SurpriseSettings::default()
+        };
+
+        assert!(settings.allows(&movie("Alien", None)));
+        assert!(settings.allows(&movie("Up", Some(10))));
+        assert!(!settings.allows(&movie("Heat", Some(1))));
+    }
+
+    #[test]
+    fn pick_only_returns_allowed_movies() {
+        let movies = [movie("Alien", Some(1)), movie("Up", None)];
+        let settings = SurpriseSettings::default();
+
+        for _ in 0..10 {
+            let picked = Surprise::pick(&movies, &settings, &[]).unwrap();
+            assert_eq!(picked.name(), "Up");
+        }
+        assert!(Surprise::pick(&movies[..1], &settings, &[]).is_none());
+    }
+}
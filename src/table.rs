@@ -0,0 +1,203 @@
+use serde::{Deserialize, Serialize};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::sort::{SortKey, SortOrder};
+
+/// Space between two columns.
+pub const SEPARATOR: &str = "  ";
+/// Narrowest the title gets before other columns are left out.
+const MIN_TITLE_WIDTH: usize = 20;
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Column {
+    Title,
+    Year,
+    Length,
+    Resolution,
+    LastWatched,
+    Rating,
+    Size,
+}
+
+impl Column {
+    const fn header(self) -> &'static str {
+        match self {
+            Self::Title => "Title",
+            Self::Year => "Year",
+            Self::Length => "Length",
+            Self::Resolution => "Resolution",
+            Self::LastWatched => "Watched",
+            Self::Rating => "Rating",
+            Self::Size => "Size",
+        }
+    }
+
+    pub const fn sort_key(self) -> SortKey {
+        match self {
+            Self::Title => SortKey::Title,
+            Self::Year => SortKey::Year,
+            Self::Length => SortKey::Length,
+            Self::Resolution => SortKey::Resolution,
+            Self::LastWatched => SortKey::LastWatched,
+            Self::Rating => SortKey::Rating,
+            Self::Size => SortKey::Size,
+        }
+    }
+
+    /// Numbers are aligned to the right.
+    const fn is_numeric(self) -> bool {
+        matches!(self, Self::Year | Self::Length | Self::Size)
+    }
+
+    /// Header of the column, with an arrow when the list is sorted by it.
+    pub fn label(self, order: SortOrder, width: usize) -> String {
+        let header = if order.key == self.sort_key() {
+            let arrow = if order.descending { "↓" } else { "↑" };
+            format!("{} {arrow}", self.header())
+        } else {
+            self.header().to_owned()
+        };
+        pad(&header, width, false)
+    }
+
+    /// Text of a cell padded to the width of the column.
+    pub fn cell(self, text: &str, width: usize) -> String {
+        pad(text, width, self.is_numeric())
+    }
+}
+
+/// How the list is laid out.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct TableSettings {
+    /// Whether the list starts as a table rather than a list of titles
+    pub enabled: bool,
+    pub columns: Vec<Column>,
+}
+
+impl Default for TableSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            columns: vec![
+                Column::Title,
+                Column::Year,
+                Column::Length,
+                Column::Resolution,
+                Column::LastWatched,
+                Column::Rating,
+                Column::Size,
+            ],
+        }
+    }
+}
+
+impl TableSettings {
+    /// Configured columns, the title always being one of them.
+    pub fn columns(&self) -> Vec<Column> {
+        let mut columns = self.columns.clone();
+        if !columns.contains(&Column::Title) {
+            columns.insert(0, Column::Title);
+        }
+        columns
+    }
+}
+
+/// Widths of the columns that fit in `available` cells of the terminal, the
+/// last ones being left out when there is not enough room. Columns are as
+/// wide as their longest cell or header, and the title takes whatever is
+/// left.
+pub fn widths(columns: &[Column], longest: &[usize], available: usize) -> Vec<(Column, usize)> {
+    let mut used = MIN_TITLE_WIDTH;
+    let mut shown = Vec::with_capacity(columns.len());
+    for (&column, &longest) in columns.iter().zip(longest) {
+        if column == Column::Title {
+            shown.push((column, 0));
+            continue;
+        }
+        // Room for the sorting arrow
+        let width = longest.max(column.header().width() + 2);
+        if used + SEPARATOR.len() + width > available {
+            break;
+        }
+        used += SEPARATOR.len() + width;
+        shown.push((column, width));
+    }
+
+    for (column, width) in &mut shown {
+        if *column == Column::Title {
+            *width = available.saturating_sub(used) + MIN_TITLE_WIDTH;
+        }
+    }
+    shown
+}
+
+/// Longest start of `text` taking at most `width` cells of the terminal,
+/// along with the cells it takes. Wide characters, e.g. CJK ones, take two.
+pub fn truncate(text: &str, width: usize) -> (&str, usize) {
+    let mut used = 0;
+    for (i, c) in text.char_indices() {
+        let char_width = c.width().unwrap_or(0);
+        if used + char_width > width {
+            return (&text[..i], used);
+        }
+        used += char_width;
+    }
+    (text, used)
+}
+
+fn pad(text: &str, width: usize, right: bool) -> String {
+    let (text, used) = truncate(text, width);
+    let padding = " ".repeat(width - used);
+    if right {
+        padding + text
+    } else {
+        text.to_owned() + &padding
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn widths_leave_out_the_last_columns_that_do_not_fit() {
+        let columns = [Column::Title, Column::Year, Column::Length, Column::Size];
+        let longest = [40, 4, 6, 8];
+
+        // Year and Length take 6 and 8 cells with the room for the arrow
+        assert!(
+            widths(&columns, &longest, 80)
+                == [
+                    (Column::Title, 52),
+                    (Column::Year, 6),
+                    (Column::Length, 8),
+                    (Column::Size, 8),
+                ]
+        );
+        assert!(
+            widths(&columns, &longest, 40)
+                == [(Column::Title, 22), (Column::Year, 6), (Column::Length, 8)]
+        );
+        assert!(widths(&columns, &longest, 10) == [(Column::Title, 20)]);
+    }
+
+    #[test]
+    fn widths_keep_the_title_where_it_is_configured() {
+        let columns = [Column::Year, Column::Title];
+
+        assert!(widths(&columns, &[4, 40], 50) == [(Column::Year, 6), (Column::Title, 42)]);
+    }
+
+    #[test]
+    fn cells_are_padded_to_their_display_width() {
+        assert_eq!(Column::Year.cell("1999", 6), "  1999");
+        assert_eq!(Column::Resolution.cell("4K", 6), "4K    ");
+        assert_eq!(Column::Resolution.cell("1080p", 3), "108");
+        // Each of these takes two cells
+        assert_eq!(Column::Resolution.cell("千と千尋", 6), "千と千");
+        assert_eq!(Column::Resolution.cell("千と千尋", 5), "千と ");
+        assert_eq!(Column::Resolution.cell("Amélie", 8), "Amélie  ");
+    }
+}
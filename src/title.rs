@@ -0,0 +1,79 @@
+use crate::movie::EXTENSIONS;
+
+/// Words that mark the end of the title in release-style file names.
+const RELEASE_TAGS: [&str; 28] = [
+    "480p", "576p", "720p", "1080p", "1080i", "2160p", "4k", "uhd", "hdr", "bluray", "bdrip",
+    "brrip", "webrip", "web", "webdl", "hdtv", "dvdrip", "remux", "x264", "x265", "h264", "h265",
+    "hevc", "xvid", "divx", "proper", "repack", "extended",
+];
+
+/// Title and year parsed from a file name such as `Alien (1979) 720p.mkv`,
+/// normalized so that different releases of the same movie compare equal.
+#[derive(PartialEq, Eq, Hash, Clone)]
+pub struct Title {
+    name: String,
+    year: Option<u16>,
+}
+
+impl Title {
+    pub const fn year(&self) -> Option<u16> {
+        self.year
+    }
+
+    fn parse_year(word: &str) -> Option<u16> {
+        (word.len() == 4)
+            .then(|| word.parse().ok())
+            .flatten()
+            .filter(|year| (1880..=2100).contains(year))
+    }
+}
+
+impl From<&str> for Title {
+    fn from(file_name: &str) -> Self {
+        let stem = file_name
+            .rsplit_once('.')
+            .filter(|(_, ext)| {
+                EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str())
+                    || ext.eq_ignore_ascii_case("iso")
+            })
+            .map_or(file_name, |(stem, _)| stem);
+
+        let words: Vec<&str> = stem
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect();
+
+        // A year in brackets wins over one that is part of the title, as in
+        // "Blade Runner 2049 (2017)". The first word is never the year.
+        let bracketed = |word: &&str| {
+            stem.contains(&format!("({word})")) || stem.contains(&format!("[{word}]"))
+        };
+        let years: Vec<(usize, &str)> = words
+            .iter()
+            .enumerate()
+            .skip(1)
+            .filter(|(_, word)| Self::parse_year(word).is_some())
+            .map(|(i, word)| (i, *word))
+            .collect();
+        let year = years
+            .iter()
+            .find(|(_, word)| bracketed(word))
+            .or_else(|| years.last())
+            .copied();
+
+        let end = year.map_or_else(
+            || {
+                words
+                    .iter()
+                    .position(|word| RELEASE_TAGS.contains(&word.to_lowercase().as_str()))
+                    .unwrap_or(words.len())
+            },
+            |(i, _)| i,
+        );
+
+        Self {
+            name: words[..end].join(" ").to_lowercase(),
+            year: year.and_then(|(_, word)| Self::parse_year(word)),
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use std::{error::Error, path::Path};
+
+use ffmpeg_next::{codec, format, media};
+use serde::{Deserialize, Serialize};
+
+/// An audio or subtitle stream of a movie file.
+#[derive(Clone)]
+pub struct Track {
+    /// Index among all the streams of the file
+    index: usize,
+    /// Position among the streams of its kind, counting from 1 like mpv
+    number: usize,
+    codec: codec::Id,
+    language: Option<String>,
+    title: Option<String>,
+}
+
+impl Track {
+    /// Describes `stream`, the `number`th of its kind in the file.
+    pub fn new(stream: &format::stream::Stream, number: usize) -> Self {
+        let metadata = stream.metadata();
+        Self {
+            index: stream.index(),
+            number,
+            codec: stream.parameters().id(),
+            language: metadata.get("language").map(str::to_owned),
+            title: metadata.get("title").map(str::to_owned),
+        }
+    }
+
+    pub const fn index(&self) -> usize {
+        self.index
+    }
+
+    pub const fn number(&self) -> usize {
+        self.number
+    }
+
+    pub const fn codec(&self) -> codec::Id {
+        self.codec
+    }
+
+    /// Language code from the stream metadata, `und` when unknown.
+    pub fn language(&self) -> &str {
+        self.language.as_deref().unwrap_or("und")
+    }
+
+    pub fn label(&self) -> String {
+        let title = self
+            .title
+            .as_ref()
+            .map(|title| format!(" ({title})"))
+            .unwrap_or_default();
+        format!(
+            "#{} {} [{}]{title}",
+            self.number,
+            self.language(),
+            self.codec.name()
+        )
+    }
+}
+
+/// Which track of a kind the player is told to use.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TrackChoice {
+    /// The first one in a preferred language, if any, or the player's choice
+    #[default]
+    Auto,
+    /// None at all, e.g. no subtitles
+    Off,
+    /// The track with the given number
+    Track(usize),
+}
+
+/// Audio and subtitle tracks picked for a movie file.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(default)]
+pub struct TrackChoices {
+    pub audio: TrackChoice,
+    pub subtitle: TrackChoice,
+}
+
+impl TrackChoices {
+    pub fn is_auto(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Audio and subtitle streams of a movie file.
+pub struct Tracks {
+    pub audio: Vec<Track>,
+    pub subtitles: Vec<Track>,
+}
+
+impl Tracks {
+    pub fn probe(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let input = format::input(&path)?;
+
+        let tracks = |medium: media::Type| {
+            input
+                .streams()
+                .filter(|stream| stream.parameters().medium() == medium)
+                .enumerate()
+                .map(|(i, stream)| Track::new(&stream, i + 1))
+                .collect()
+        };
+
+        Ok(Self {
+            audio: tracks(media::Type::Audio),
+            subtitles: tracks(media::Type::Subtitle),
+        })
+    }
+}
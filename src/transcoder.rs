@@ -0,0 +1,625 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use ffmpeg_next::{
+    ChannelLayout, Dictionary, Packet, Rational, codec, decoder, encoder, filter, format, frame,
+    media, software::scaling,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::Config,
+    movie::{EXTENSIONS, MovieLength},
+    verifier::received,
+};
+
+/// Format that converted copies are made in. Streams already in the target
+/// codec are copied as they are, the others are re-encoded.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Profile {
+    /// Added to the name of converted files, e.g. `Alien (1979).tv.mp4`
+    pub name: String,
+    /// Extension of converted files, which also selects the muxer
+    pub container: String,
+    /// Encoder for video streams in another codec
+    pub video_encoder: String,
+    /// Options passed to the video encoder, e.g. `crf` and `preset`
+    pub video_options: BTreeMap<String, String>,
+    /// Encoder for audio streams in another codec
+    pub audio_encoder: String,
+    /// Bit rate of re-encoded audio, in bits per second
+    pub audio_bit_rate: usize,
+    /// Channels of re-encoded audio, 2 to downmix surround sound
+    pub audio_channels: u16,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            name: "tv".to_string(),
+            container: "mp4".to_string(),
+            video_encoder: "libx264".to_string(),
+            video_options: BTreeMap::from([
+                ("crf".to_string(), "20".to_string()),
+                ("preset".to_string(), "medium".to_string()),
+            ]),
+            audio_encoder: "aac".to_string(),
+            audio_bit_rate: 192_000,
+            audio_channels: 2,
+        }
+    }
+}
+
+/// How an input stream ends up in the output.
+enum Route {
+    Copy,
+    Video {
+        decoder: decoder::Video,
+        encoder: encoder::video::Encoder,
+        scaler: Option<scaling::Context>,
+    },
+    Audio {
+        decoder: decoder::Audio,
+        encoder: encoder::audio::Encoder,
+        filter: filter::Graph,
+    },
+}
+
+/// An input stream kept in the output.
+struct Mapping {
+    input: usize,
+    output: usize,
+    input_time_base: Rational,
+    /// Time base of the packets coming out of the encoder, if any
+    encoder_time_base: Rational,
+    output_time_base: Rational,
+    route: Route,
+}
+
+impl Mapping {
+    fn write(
+        &mut self,
+        packet: &mut Packet,
+        output: &mut format::context::Output,
+    ) -> Result<(), Box<dyn Error>> {
+        if matches!(self.route, Route::Copy) {
+            Self::write_packet(
+                packet,
+                self.output,
+                self.input_time_base,
+                self.output_time_base,
+                output,
+            )?;
+            return Ok(());
+        }
+
+        self.decoder().send_packet(packet)?;
+        self.drain(output)
+    }
+
+    fn finish(&mut self, output: &mut format::context::Output) -> Result<(), Box<dyn Error>> {
+        if matches!(self.route, Route::Copy) {
+            return Ok(());
+        }
+
+        self.decoder().send_eof()?;
+        self.drain(output)?;
+        if let Route::Audio { filter, .. } = &mut self.route {
+            filter
+                .get("in")
+                .ok_or("Audio filter not found")?
+                .source()
+                .flush()?;
+        }
+        self.drain(output)?;
+        self.encoder().send_eof()?;
+        self.receive_packets(output)
+    }
+
+    /// Passes every frame the decoder has ready through to the encoder.
+    fn drain(&mut self, output: &mut format::context::Output) -> Result<(), Box<dyn Error>> {
+        match &mut self.route {
+            Route::Copy => {}
+            Route::Video {
+                decoder,
+                encoder,
+                scaler,
+            } => {
+                let mut decoded = frame::Video::empty();
+                let mut converted = frame::Video::empty();
+                while received(decoder.receive_frame(&mut decoded))? {
+                    let timestamp = decoded.timestamp();
+                    let frame = if decoded.format() == format::Pixel::YUV420P {
+                        &mut decoded
+                    } else {
+                        let scaler = match scaler {
+                            Some(scaler) => scaler,
+                            None => scaler.insert(scaling::Context::get(
+                                decoded.format(),
+                                decoded.width(),
+                                decoded.height(),
+                                format::Pixel::YUV420P,
+                                decoded.width(),
+                                decoded.height(),
+                                scaling::Flags::BILINEAR,
+                            )?),
+                        };
+                        scaler.run(&decoded, &mut converted)?;
+                        &mut converted
+                    };
+                    frame.set_pts(timestamp);
+                    encoder.send_frame(frame)?;
+                    Self::write_encoded(
+                        encoder,
+                        self.output,
+                        self.encoder_time_base,
+                        self.output_time_base,
+                        output,
+                    )?;
+                }
+            }
+            Route::Audio {
+                decoder,
+                encoder,
+                filter,
+            } => {
+                let mut decoded = frame::Audio::empty();
+                while received(decoder.receive_frame(&mut decoded))? {
+                    let timestamp = decoded.timestamp();
+                    decoded.set_pts(timestamp);
+                    filter
+                        .get("in")
+                        .ok_or("Audio filter not found")?
+                        .source()
+                        .add(&decoded)?;
+                }
+
+                let mut filtered = frame::Audio::empty();
+                let mut sink = filter.get("out").ok_or("Audio filter not found")?;
+                while received(sink.sink().frame(&mut filtered))? {
+                    encoder.send_frame(&filtered)?;
+                    Self::write_encoded(
+                        encoder,
+                        self.output,
+                        self.encoder_time_base,
+                        self.output_time_base,
+                        output,
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn receive_packets(
+        &mut self,
+        output: &mut format::context::Output,
+    ) -> Result<(), Box<dyn Error>> {
+        let (output_index, encoder_time_base, output_time_base) =
+            (self.output, self.encoder_time_base, self.output_time_base);
+        Self::write_encoded(
+            self.encoder(),
+            output_index,
+            encoder_time_base,
+            output_time_base,
+            output,
+        )
+    }
+
+    fn write_encoded(
+        encoder: &mut encoder::Encoder,
+        index: usize,
+        encoder_time_base: Rational,
+        output_time_base: Rational,
+        output: &mut format::context::Output,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut packet = Packet::empty();
+        while received(encoder.receive_packet(&mut packet))? {
+            Self::write_packet(
+                &mut packet,
+                index,
+                encoder_time_base,
+                output_time_base,
+                output,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn write_packet(
+        packet: &mut Packet,
+        index: usize,
+        time_base: Rational,
+        output_time_base: Rational,
+        output: &mut format::context::Output,
+    ) -> Result<(), Box<dyn Error>> {
+        packet.rescale_ts(time_base, output_time_base);
+        packet.set_position(-1);
+        packet.set_stream(index);
+        packet.write_interleaved(output)?;
+        Ok(())
+    }
+
+    fn decoder(&mut self) -> &mut decoder::Opened {
+        match &mut self.route {
+            Route::Video { decoder, .. } => decoder,
+            Route::Audio { decoder, .. } => decoder,
+            Route::Copy => unreachable!("copied streams are not decoded"),
+        }
+    }
+
+    fn encoder(&mut self) -> &mut encoder::Encoder {
+        match &mut self.route {
+            Route::Video { encoder, .. } => encoder,
+            Route::Audio { encoder, .. } => encoder,
+            Route::Copy => unreachable!("copied streams are not encoded"),
+        }
+    }
+}
+
+pub struct Transcoder;
+
+impl Transcoder {
+    /// Converts a file to the profile next to the original, calling
+    /// `progress` with the percentage done as it goes. Returns the converted
+    /// copy, or `None` when the file already is in the profile's formats.
+    /// A partial file is removed on failure.
+    pub fn convert(
+        path: &Path,
+        profile: &Profile,
+        mut progress: impl FnMut(u64),
+    ) -> Result<Option<PathBuf>, Box<dyn Error>> {
+        let output_path = Self::output_path(path, profile)?;
+        let mut input = format::input(&path)?;
+
+        // The output is closed before a partial file gets removed
+        let result = format::output(&output_path)
+            .map_err(Into::into)
+            .and_then(|mut output| {
+                Self::run(path, profile, &mut input, &mut output, &mut progress)
+            });
+        match result {
+            Ok(true) => Ok(Some(output_path)),
+            Ok(false) => {
+                fs::remove_file(&output_path).ok();
+                Ok(None)
+            }
+            Err(e) => {
+                fs::remove_file(&output_path).ok();
+                Err(e)
+            }
+        }
+    }
+
+    /// `<movie>.<profile>.<container>` next to the movie.
+    pub fn output_path(path: &Path, profile: &Profile) -> Result<PathBuf, Box<dyn Error>> {
+        let stem = path
+            .file_stem()
+            .ok_or("Path does not have a valid filename")?
+            .to_string_lossy();
+        Ok(path.with_file_name(format!("{stem}.{}.{}", profile.name, profile.container)))
+    }
+
+    /// Whether the file is the converted copy of a movie next to it, which
+    /// is not a movie of its own.
+    pub fn is_output(path: &Path) -> bool {
+        let profile = &Config::get().transcode;
+        let suffix = format!(".{}", profile.name);
+
+        let Some(stem) = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.strip_suffix(&suffix))
+        else {
+            return false;
+        };
+
+        path.extension().and_then(|ext| ext.to_str()) == Some(profile.container.as_str())
+            && EXTENSIONS
+                .iter()
+                .any(|ext| path.with_file_name(format!("{stem}.{ext}")).exists())
+    }
+
+    fn run(
+        path: &Path,
+        profile: &Profile,
+        input: &mut format::context::Input,
+        output: &mut format::context::Output,
+        progress: &mut impl FnMut(u64),
+    ) -> Result<bool, Box<dyn Error>> {
+        let length = MovieLength::from_input(input).max(1);
+        let video_index = input
+            .streams()
+            .best(media::Type::Video)
+            .ok_or("No video stream")?
+            .index();
+        let mut mappings = Self::map_streams(input, output, video_index, profile)?;
+
+        let source_container = path.extension().and_then(|ext| ext.to_str());
+        if source_container == Some(profile.container.as_str())
+            && mappings
+                .iter()
+                .all(|mapping| matches!(mapping.route, Route::Copy))
+        {
+            return Ok(false);
+        }
+
+        output.set_metadata(input.metadata().to_owned());
+        output.write_header()?;
+        // The muxer may pick other time bases than the ones asked for
+        for mapping in &mut mappings {
+            mapping.output_time_base = output
+                .stream(mapping.output)
+                .ok_or("Output stream not found")?
+                .time_base();
+        }
+
+        let mut packet = Packet::empty();
+        let mut done = None;
+        loop {
+            match packet.read(input) {
+                Ok(()) => {}
+                Err(ffmpeg_next::Error::Eof) => break,
+                Err(e) => return Err(e.into()),
+            }
+            let Some(mapping) = mappings
+                .iter_mut()
+                .find(|mapping| mapping.input == packet.stream())
+            else {
+                continue;
+            };
+
+            if mapping.input == video_index
+                && let Some(pts) = packet.pts()
+            {
+                let time_base = mapping.input_time_base;
+                let position = pts.max(0).cast_unsigned()
+                    * u64::from(time_base.numerator().cast_unsigned())
+                    / u64::from(time_base.denominator().max(1).cast_unsigned());
+                let percent = (position * 100 / length).min(100);
+                if done != Some(percent) {
+                    done = Some(percent);
+                    progress(percent);
+                }
+            }
+
+            mapping.write(&mut packet, output)?;
+        }
+
+        for mapping in &mut mappings {
+            mapping.finish(output)?;
+        }
+        output.write_trailer()?;
+        Ok(true)
+    }
+
+    /// Sets up the output streams. Only the main video stream and the audio
+    /// streams are kept, the target containers rarely support the other kinds
+    /// as they are.
+    fn map_streams(
+        input: &format::context::Input,
+        output: &mut format::context::Output,
+        video_index: usize,
+        profile: &Profile,
+    ) -> Result<Vec<Mapping>, Box<dyn Error>> {
+        let video_codec = encoder::find_by_name(&profile.video_encoder)
+            .ok_or_else(|| format!("Encoder {} not available", profile.video_encoder))?;
+        let audio_codec = encoder::find_by_name(&profile.audio_encoder)
+            .ok_or_else(|| format!("Encoder {} not available", profile.audio_encoder))?;
+        let global_header = output
+            .format()
+            .flags()
+            .contains(format::Flags::GLOBAL_HEADER);
+
+        let mut mappings = Vec::new();
+        for stream in input.streams() {
+            let medium = stream.parameters().medium();
+            if stream.index() != video_index && medium != media::Type::Audio {
+                continue;
+            }
+            let target = if medium == media::Type::Video {
+                video_codec
+            } else {
+                audio_codec
+            };
+
+            let output_index = mappings.len();
+            let mapping = if stream.parameters().id() == target.id() {
+                Self::copy_mapping(&stream, output, output_index)?
+            } else if medium == media::Type::Video {
+                Self::video_mapping(
+                    &stream,
+                    output,
+                    output_index,
+                    target,
+                    profile,
+                    global_header,
+                )?
+            } else {
+                Self::audio_mapping(
+                    &stream,
+                    output,
+                    output_index,
+                    target,
+                    profile,
+                    global_header,
+                )?
+            };
+            mappings.push(mapping);
+        }
+
+        Ok(mappings)
+    }
+
+    fn copy_mapping(
+        stream: &format::stream::Stream,
+        output: &mut format::context::Output,
+        output_index: usize,
+    ) -> Result<Mapping, Box<dyn Error>> {
+        let mut output_stream = output.add_stream(encoder::find(codec::Id::None))?;
+        output_stream.set_parameters(stream.parameters());
+        // SAFETY: the parameters belong to the stream that was just added.
+        // The tag of the source container may not be valid in the target one,
+        // so it is left to the muxer
+        unsafe {
+            (*output_stream.parameters().as_mut_ptr()).codec_tag = 0;
+        }
+        output_stream.set_metadata(stream.metadata().to_owned());
+
+        Ok(Mapping {
+            input: stream.index(),
+            output: output_index,
+            input_time_base: stream.time_base(),
+            encoder_time_base: stream.time_base(),
+            output_time_base: stream.time_base(),
+            route: Route::Copy,
+        })
+    }
+
+    fn video_mapping(
+        stream: &format::stream::Stream,
+        output: &mut format::context::Output,
+        output_index: usize,
+        codec: codec::Codec,
+        profile: &Profile,
+        global_header: bool,
+    ) -> Result<Mapping, Box<dyn Error>> {
+        let decoder = codec::context::Context::from_parameters(stream.parameters())?
+            .decoder()
+            .video()?;
+
+        let mut encoder = codec::context::Context::new_with_codec(codec)
+            .encoder()
+            .video()?;
+        encoder.set_width(decoder.width());
+        encoder.set_height(decoder.height());
+        encoder.set_aspect_ratio(decoder.aspect_ratio());
+        encoder.set_format(format::Pixel::YUV420P);
+        // Streams with a variable frame rate report none
+        encoder.set_frame_rate(Some(stream.avg_frame_rate()).filter(|rate| rate.numerator() > 0));
+        encoder.set_time_base(stream.time_base());
+        if global_header {
+            encoder.set_flags(codec::Flags::GLOBAL_HEADER);
+        }
+
+        let mut options = Dictionary::new();
+        for (key, value) in &profile.video_options {
+            options.set(key, value);
+        }
+        let encoder = encoder.open_with(options)?;
+
+        let mut output_stream = output.add_stream(codec)?;
+        output_stream.set_parameters(&encoder);
+        output_stream.set_time_base(stream.time_base());
+        output_stream.set_metadata(stream.metadata().to_owned());
+
+        Ok(Mapping {
+            input: stream.index(),
+            output: output_index,
+            input_time_base: stream.time_base(),
+            encoder_time_base: stream.time_base(),
+            output_time_base: stream.time_base(),
+            route: Route::Video {
+                decoder,
+                encoder,
+                scaler: None,
+            },
+        })
+    }
+
+    fn audio_mapping(
+        stream: &format::stream::Stream,
+        output: &mut format::context::Output,
+        output_index: usize,
+        codec: codec::Codec,
+        profile: &Profile,
+        global_header: bool,
+    ) -> Result<Mapping, Box<dyn Error>> {
+        let decoder = codec::context::Context::from_parameters(stream.parameters())?
+            .decoder()
+            .audio()?;
+        let sample_format = codec
+            .audio()?
+            .formats()
+            .and_then(|mut formats| formats.next())
+            .ok_or("Audio encoder has no sample format")?;
+        let rate = decoder.rate();
+        let time_base = Rational::new(1, i32::try_from(rate)?);
+
+        let mut encoder = codec::context::Context::new_with_codec(codec)
+            .encoder()
+            .audio()?;
+        encoder.set_rate(i32::try_from(rate)?);
+        encoder.set_channel_layout(ChannelLayout::default(i32::from(profile.audio_channels)));
+        encoder.set_format(sample_format);
+        encoder.set_bit_rate(profile.audio_bit_rate);
+        encoder.set_time_base(time_base);
+        if global_header {
+            encoder.set_flags(codec::Flags::GLOBAL_HEADER);
+        }
+        let encoder = encoder.open()?;
+
+        // The filter converts the samples and cuts them into frames of the
+        // size the encoder expects
+        let layout = decoder.channel_layout();
+        let source_layout = if layout.is_empty() {
+            format!("channels={}", decoder.channels())
+        } else {
+            format!("channel_layout=0x{:x}", layout.bits())
+        };
+        let mut filter = filter::Graph::new();
+        filter.add(
+            &filter::find("abuffer").ok_or("Audio filters not available")?,
+            "in",
+            &format!(
+                "time_base={}:sample_rate={rate}:sample_fmt={}:{source_layout}",
+                stream.time_base(),
+                decoder.format().name()
+            ),
+        )?;
+        filter.add(
+            &filter::find("abuffersink").ok_or("Audio filters not available")?,
+            "out",
+            "",
+        )?;
+        filter.output("in", 0)?.input("out", 0)?.parse(&format!(
+            "aresample={rate},aformat=sample_fmts={}:channel_layouts={}c,asettb=1/{rate}",
+            sample_format.name(),
+            profile.audio_channels
+        ))?;
+        filter.validate()?;
+        if !codec
+            .capabilities()
+            .contains(codec::capabilities::Capabilities::VARIABLE_FRAME_SIZE)
+        {
+            filter
+                .get("out")
+                .ok_or("Audio filter not found")?
+                .sink()
+                .set_frame_size(encoder.frame_size());
+        }
+
+        let mut output_stream = output.add_stream(codec)?;
+        output_stream.set_parameters(&encoder);
+        output_stream.set_time_base(time_base);
+        output_stream.set_metadata(stream.metadata().to_owned());
+
+        Ok(Mapping {
+            input: stream.index(),
+            output: output_index,
+            input_time_base: stream.time_base(),
+            encoder_time_base: time_base,
+            output_time_base: time_base,
+            route: Route::Audio {
+                decoder,
+                encoder,
+                filter,
+            },
+        })
+    }
+}
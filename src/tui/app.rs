@@ -6,18 +6,28 @@ use cursive::{
     theme::{BorderStyle, Palette},
     view::Resizable,
     views::{
-        Dialog, DummyView, LinearLayout, NamedView, OnEventView, Panel, ScrollView, SelectView,
-        TextView,
+        Dialog, DummyView, LinearLayout, NamedView, OnEventView, ScrollView, SelectView, TextView,
     },
 };
 
 use crate::{
     archive::Archive,
     tui::{
+        budget_view::BudgetView,
+        contact_sheet_view::ContactSheetView,
+        duplicates_view::DuplicatesView,
         filter_view::FilterView,
         info_view::InfoView,
-        list_view::{ListView, SCROLL_ID, SELECT_ID},
+        list_view::{Item, ListView, SCROLL_ID, SELECT_ID},
+        poster_view::PosterView,
+        query_view::QueryView,
+        queue_view::QueueView,
+        search_view::SearchView,
         stats_view::StatsView,
+        subtitles_view::SubtitlesView,
+        surprise_view::SurpriseView,
+        tags_view::TagsView,
+        tracks_view::TracksView,
         user_data::UserData,
     },
 };
@@ -25,9 +35,32 @@ use crate::{
 const HELP_KEYBINDS: &[&str] = &[
     "w -> mark as watched",
     "p -> play a movie",
+    "A -> choose audio and subtitle tracks",
+    "a -> add to the play queue",
+    "P -> show the play queue",
+    "r -> pick a random movie",
+    "T -> edit a movie's tags",
+    "/ -> search the list",
+    "n/N -> jump to the next/previous match",
+    "ENTER -> expand/collapse a series",
+    "v -> switch preferred version",
+    "D -> show duplicate files",
+    "i -> verify a movie's integrity",
+    "I -> verify all movies",
+    "e -> detect end credits",
+    "E -> detect end credits of all movies",
+    "c -> show contact sheet",
+    "x -> extract subtitles",
+    "t -> convert for other devices",
     "? -> show this dialog",
     "q -> quit",
-    "s -> toggle watched filter",
+    "s -> cycle filter (not watched, continue watching, watched, none)",
+    "f -> filter with a query, e.g. unwatched length<2h tag:horror",
+    "F -> show saved smart lists",
+    "o -> cycle sort order (not watched first, title, length, last watched, date added, size, resolution, year, rating)",
+    "O -> toggle ascending/descending order",
+    "m -> switch between a table and a list of titles",
+    "b -> only list what fits before a time",
     "ESC -> go back",
 ];
 
@@ -76,6 +109,7 @@ impl App {
         let filter_view = FilterView::new();
         let info_view = InfoView::new();
         let stats_view = StatsView::new();
+        let poster_view = PosterView::new();
 
         siv.add_fullscreen_layer(
             LinearLayout::vertical()
@@ -92,7 +126,7 @@ impl App {
                     LinearLayout::horizontal().child(list_view).child(
                         LinearLayout::vertical()
                             .child(info_view)
-                            .child(Panel::new(DummyView).full_height())
+                            .child(poster_view.full_height())
                             .child(stats_view)
                             .fixed_width(19),
                     ),
@@ -102,7 +136,17 @@ impl App {
         ListView::refresh(&mut siv);
         FilterView::refresh(&mut siv);
 
-        siv.run();
+        // Images drawn with a terminal graphics protocol have to be written
+        // after cursive is done with the screen
+        let mut runner = siv.runner();
+        runner.refresh();
+        PosterView::draw_graphics(&mut runner);
+        while runner.is_running() {
+            if runner.step() {
+                PosterView::draw_graphics(&mut runner);
+            }
+        }
+        PosterView::draw_graphics(&mut runner);
     }
 
     fn setup_keybinds(siv: &mut cursive::CursiveRunnable) {
@@ -113,7 +157,7 @@ impl App {
         let move_and_refresh = |s: &mut Cursive, direction: i32| {
             let mut current_name = None;
 
-            s.call_on_name(SELECT_ID, |v: &mut SelectView| {
+            s.call_on_name(SELECT_ID, |v: &mut SelectView<Item>| {
                 let steps = direction.unsigned_abs() as usize;
                 if direction > 0 {
                     v.select_down(steps);
@@ -130,29 +174,61 @@ impl App {
                 InfoView::refresh(s);
             }
 
-            s.call_on_name(SCROLL_ID, |v: &mut ScrollView<NamedView<SelectView>>| {
-                v.scroll_to_left();
-                v.scroll_to_important_area();
-            });
+            s.call_on_name(
+                SCROLL_ID,
+                |v: &mut ScrollView<NamedView<SelectView<Item>>>| {
+                    v.scroll_to_left();
+                    v.scroll_to_important_area();
+                },
+            );
         };
 
         siv.add_global_callback('j', move |s| move_and_refresh(s, 1));
         siv.add_global_callback('k', move |s| move_and_refresh(s, -1));
 
         siv.add_global_callback('h', |siv| {
-            siv.call_on_name(SCROLL_ID, |v: &mut ScrollView<NamedView<SelectView>>| {
-                v.scroll_to_left();
-            });
+            siv.call_on_name(
+                SCROLL_ID,
+                |v: &mut ScrollView<NamedView<SelectView<Item>>>| {
+                    v.scroll_to_left();
+                },
+            );
         });
         siv.add_global_callback('l', |siv| {
-            siv.call_on_name(SCROLL_ID, |v: &mut ScrollView<NamedView<SelectView>>| {
-                v.scroll_to_right();
-            });
+            siv.call_on_name(
+                SCROLL_ID,
+                |v: &mut ScrollView<NamedView<SelectView<Item>>>| {
+                    v.scroll_to_right();
+                },
+            );
         });
 
         siv.add_global_callback('w', ListView::toggle_watched);
         siv.add_global_callback('p', ListView::play_movie);
+        siv.add_global_callback('A', TracksView::show);
+        siv.add_global_callback('a', ListView::enqueue);
+        siv.add_global_callback('P', QueueView::show);
+        siv.add_global_callback('r', SurpriseView::show);
+        siv.add_global_callback('T', TagsView::show);
+        siv.add_global_callback('/', SearchView::show);
+        siv.add_global_callback('n', |siv| ListView::jump_to_match(siv, true));
+        siv.add_global_callback('N', |siv| ListView::jump_to_match(siv, false));
         siv.add_global_callback('s', FilterView::change_filter);
+        siv.add_global_callback('f', QueryView::show);
+        siv.add_global_callback('F', QueryView::show_smart_lists);
+        siv.add_global_callback('o', FilterView::change_sort);
+        siv.add_global_callback('O', FilterView::toggle_sort_direction);
+        siv.add_global_callback('m', ListView::toggle_table);
+        siv.add_global_callback('b', BudgetView::show);
+        siv.add_global_callback('v', ListView::cycle_version);
+        siv.add_global_callback('D', DuplicatesView::show);
+        siv.add_global_callback('i', ListView::verify_selected);
+        siv.add_global_callback('I', ListView::verify_all);
+        siv.add_global_callback('e', ListView::detect_credits_selected);
+        siv.add_global_callback('E', ListView::detect_credits_all);
+        siv.add_global_callback('c', ContactSheetView::show);
+        siv.add_global_callback('x', SubtitlesView::show);
+        siv.add_global_callback('t', ListView::queue_transcode);
     }
 
     fn show_keybinds(siv: &mut Cursive) {
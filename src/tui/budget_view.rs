@@ -0,0 +1,72 @@
+use cursive::{
+    Cursive,
+    event::{Event, Key},
+    view::{Nameable, Resizable},
+    views::{Dialog, EditView, LinearLayout, OnEventView, TextView},
+};
+
+use crate::{
+    budget::TimeBudget,
+    tui::{filter_view::FilterView, list_view::ListView, user_data::UserData},
+};
+
+const UNTIL_ID: &str = "budget_until";
+
+pub struct BudgetView;
+
+impl BudgetView {
+    /// Asks until when there is time to watch something, to only list what
+    /// fits before then.
+    pub fn show(siv: &mut Cursive) {
+        let until = siv
+            .user_data::<UserData>()
+            .and_then(|user_data| user_data.budget())
+            .map(|budget| budget.pretty_deadline())
+            .unwrap_or_default();
+
+        let content = LinearLayout::horizontal()
+            .child(TextView::new("Watch until (HH:MM) "))
+            .child(
+                EditView::new()
+                    .content(until)
+                    .on_submit(Self::set)
+                    .with_name(UNTIL_ID)
+                    .fixed_width(8),
+            );
+        let dialog = Dialog::around(content)
+            .title("Time budget")
+            .button("Set", |siv| {
+                let until = siv
+                    .call_on_name(UNTIL_ID, |e: &mut EditView| e.get_content())
+                    .unwrap_or_default();
+                Self::set(siv, &until);
+            })
+            .button("Clear", |siv| Self::set(siv, ""))
+            .dismiss_button("Cancel");
+
+        siv.add_layer(
+            OnEventView::new(dialog).on_pre_event(Event::Key(Key::Esc), |app| {
+                app.pop_layer();
+            }),
+        );
+    }
+
+    /// Sets the deadline, or leaves the budget mode if `until` is empty.
+    fn set(siv: &mut Cursive, until: &str) {
+        let budget = match until.trim() {
+            "" => None,
+            until => match TimeBudget::parse(until, TimeBudget::now()) {
+                Ok(budget) => Some(budget),
+                Err(e) => {
+                    siv.add_layer(Dialog::info(format!("Invalid time: {e}")));
+                    return;
+                }
+            },
+        };
+        siv.pop_layer();
+
+        siv.with_user_data(|user_data: &mut UserData| user_data.set_budget(budget));
+        FilterView::refresh(siv);
+        ListView::refresh(siv);
+    }
+}
@@ -0,0 +1,60 @@
+use std::path::PathBuf;
+
+use cursive::{
+    Cursive,
+    event::{Event, Key},
+    view::{Nameable, Resizable},
+    views::{Dialog, OnEventView},
+};
+
+use crate::{
+    contact_sheet::ContactSheet,
+    tui::{
+        jobs::Jobs,
+        list_view::{Item, ListView},
+        poster_view::Poster,
+        user_data::UserData,
+    },
+};
+
+pub const CONTACT_SHEET_ID: &str = "contact_sheet";
+/// Identifier of the contact sheet image for graphics protocols that need one.
+const CONTACT_SHEET_IMAGE_ID: u32 = 2;
+
+pub struct ContactSheetView;
+
+impl ContactSheetView {
+    /// Shows the contact sheet of the selected movie, generating it in the
+    /// background first if needed.
+    pub fn show(siv: &mut Cursive) {
+        let Some(Item::Movie(name)) = ListView::get_selected(siv) else {
+            return;
+        };
+        let Some(movie) = siv
+            .user_data::<UserData>()
+            .map(|user_data| user_data.archive().get_movie(&name).clone())
+        else {
+            return;
+        };
+
+        match ContactSheet::find(&movie) {
+            Some(path) => Self::open(siv, path),
+            None => Jobs::contact_sheet(siv, movie),
+        }
+    }
+
+    pub fn open(siv: &mut Cursive, path: PathBuf) {
+        let dialog = Dialog::new().title("Contact sheet").content(
+            Poster::new(Some(path), CONTACT_SHEET_IMAGE_ID)
+                .with_name(CONTACT_SHEET_ID)
+                .full_screen(),
+        );
+
+        siv.add_layer(
+            OnEventView::new(dialog).on_pre_event(Event::Key(Key::Esc), |app| {
+                app.call_on_name(CONTACT_SHEET_ID, Poster::hide);
+                app.pop_layer();
+            }),
+        );
+    }
+}
@@ -0,0 +1,49 @@
+use std::thread;
+
+use cursive::{
+    Cursive,
+    event::{Event, Key},
+    view::{Nameable, Resizable, Scrollable},
+    views::{Dialog, OnEventView, TextView},
+};
+
+use crate::{duplicates::DuplicateReport, tui::user_data::UserData};
+
+const DUPLICATES_ID: &str = "duplicates";
+
+pub struct DuplicatesView;
+
+impl DuplicatesView {
+    /// Opens a dialog listing byte-identical files, scanned in the background
+    /// since every candidate has to be read in full.
+    pub fn show(siv: &mut Cursive) {
+        let Some(archive) = siv.user_data::<UserData>().map(|d| d.archive().clone()) else {
+            return;
+        };
+
+        let dialog = Dialog::new().title("Duplicates").content(
+            TextView::new("Scanning for duplicates...")
+                .with_name(DUPLICATES_ID)
+                .scrollable()
+                .max_height(20),
+        );
+
+        siv.add_layer(
+            OnEventView::new(dialog).on_pre_event(Event::Key(Key::Esc), |app| {
+                app.pop_layer();
+            }),
+        );
+
+        let cb = siv.cb_sink().clone();
+        thread::spawn(move || {
+            let report = DuplicateReport::scan(&archive).to_string();
+
+            cb.send(Box::new(move |siv| {
+                siv.call_on_name(DUPLICATES_ID, |v: &mut TextView| {
+                    v.set_content(report);
+                });
+            }))
+            .ok();
+        });
+    }
+}
@@ -1,4 +1,4 @@
-use std::fmt::Display;
+use std::{fmt::Display, time::SystemTime};
 
 use cursive::{
     Cursive,
@@ -7,7 +7,12 @@ use cursive::{
     wrap_impl,
 };
 
-use crate::tui::{list_view::ListView, user_data::UserData};
+use crate::{
+    budget::TimeBudget,
+    movie,
+    sort::{SortKey, SortOrder},
+    tui::{list_view::ListView, user_data::UserData},
+};
 
 const FILTER_ID: &str = "filter";
 
@@ -15,6 +20,8 @@ const FILTER_ID: &str = "filter";
 pub enum Filter {
     Watched,
     NotWatched,
+    /// Started but not finished
+    InProgress,
     Empty,
 }
 
@@ -23,15 +30,27 @@ impl Display for Filter {
         match *self {
             Self::Watched => write!(f, "Watched"),
             Self::NotWatched => write!(f, "Not watched"),
+            Self::InProgress => write!(f, "Continue watching"),
             Self::Empty => write!(f, "None"),
         }
     }
 }
 
 impl Filter {
+    /// Whether an entry with the given watch state is shown.
+    pub const fn matches(self, since_watched: Option<SystemTime>, in_progress: bool) -> bool {
+        match self {
+            Self::NotWatched => since_watched.is_none(),
+            Self::InProgress => in_progress,
+            Self::Watched => since_watched.is_some(),
+            Self::Empty => true,
+        }
+    }
+
     const fn cycle(&mut self) {
         *self = match self {
-            Self::NotWatched => Self::Watched,
+            Self::NotWatched => Self::InProgress,
+            Self::InProgress => Self::Watched,
             Self::Watched => Self::Empty,
             Self::Empty => Self::NotWatched,
         };
@@ -57,13 +76,62 @@ impl FilterView {
     }
 
     pub fn refresh(siv: &mut Cursive) {
-        let filter = siv.user_data::<UserData>().unwrap().filter().to_string();
+        let user_data = siv.user_data::<UserData>().unwrap();
+        let filter = user_data.filter().to_string();
+        let sort = user_data.archive().sort();
+        let query = user_data
+            .query()
+            .map(|query| format!(" + {query}"))
+            .unwrap_or_default();
+        let budget = user_data
+            .budget()
+            .map(|budget| {
+                format!(
+                    " | Until {} ({} left)",
+                    budget.pretty_deadline(),
+                    movie::pretty_length(budget.remaining(TimeBudget::now()))
+                )
+            })
+            .unwrap_or_default();
+        let jobs = user_data
+            .jobs()
+            .filter(|progress| !progress.is_empty())
+            .map(|progress| " | ".to_owned() + progress)
+            .collect::<String>();
 
         if let Some(mut view) = siv.find_name::<TextView>(FILTER_ID) {
-            view.set_content("Filter: ".to_owned() + &filter);
+            view.set_content(format!(
+                "Filter: {filter}{query} | Sort: {sort}{budget}{jobs}"
+            ));
         }
     }
 
+    /// Sorts the list by the next key.
+    pub fn change_sort(siv: &mut Cursive) {
+        Self::update_sort(siv, SortOrder::cycle_key);
+    }
+
+    /// Switches between ascending and descending order.
+    pub fn toggle_sort_direction(siv: &mut Cursive) {
+        Self::update_sort(siv, SortOrder::toggle_direction);
+    }
+
+    /// Sorts the list by a key, or switches the direction if it already is.
+    pub fn sort_by(siv: &mut Cursive, key: SortKey) {
+        Self::update_sort(siv, |order| order.sort_by(key));
+    }
+
+    fn update_sort(siv: &mut Cursive, update: impl FnOnce(&mut SortOrder)) {
+        siv.with_user_data(|user_data: &mut UserData| {
+            let archive = user_data.archive_mut();
+            update(archive.sort_mut());
+            archive.save().ok();
+        });
+
+        Self::refresh(siv);
+        ListView::refresh(siv);
+    }
+
     pub fn change_filter(siv: &mut Cursive) {
         siv.with_user_data(|user_data: &mut UserData| {
             user_data.filter_mut().cycle();
@@ -4,7 +4,19 @@ use cursive::{
     views::{NamedView, Panel, TextView},
 };
 
-use crate::tui::{list_view::ListView, user_data::UserData};
+use crate::{
+    budget::{self, TimeBudget},
+    config::Config,
+    movie,
+    series::Episode,
+    session::{self, Session},
+    tui::{
+        list_view::{Item, ListView},
+        poster_view::PosterView,
+        user_data::UserData,
+    },
+    verifier::Integrity,
+};
 
 pub const INFO_ID: &str = "info";
 
@@ -27,26 +39,132 @@ impl InfoView {
     }
 
     pub fn refresh(siv: &mut Cursive) {
-        let name = &ListView::get_selected_name(siv);
-
-        let movie_data = siv.user_data().and_then(|d: &mut UserData| {
-            d.archive_mut()
-                .movies
-                .iter()
-                .find(|m| m.name() == *name)
-                .map(|m| {
+        let Some(item) = ListView::get_selected(siv) else {
+            return;
+        };
+
+        let data = siv.user_data().and_then(|d: &mut UserData| {
+            let archive = d.archive();
+            let budget = d.budget();
+            match item {
+                Item::Movie(name) => archive.movies.iter().find(|m| m.name() == name).map(|m| {
+                    let parts = match m.num_parts() {
+                        1 => String::new(),
+                        n => format!("\nPARTS: {n}"),
+                    };
+                    let quality = m
+                        .resolution()
+                        .map(|r| format!("\nQUALITY: {}", r.pretty()))
+                        .unwrap_or_default();
+                    let versions = match m.versions().len() {
+                        0 => String::new(),
+                        n => format!("\nVERSIONS: {}", n + 1),
+                    };
+                    let integrity = match m.integrity() {
+                        None => String::new(),
+                        Some(Integrity::Ok) => "\nINTEGRITY: OK".to_string(),
+                        Some(Integrity::Broken { position, error }) => format!(
+                            "\nINTEGRITY: BROKEN\nAT: {}\n{error}",
+                            movie::pretty_position(*position)
+                        ),
+                    };
+                    let credits = m
+                        .credits_start()
+                        .filter(|&start| start < m.length())
+                        .map(|start| format!("\nCREDITS: {}", movie::pretty_position(start)))
+                        .unwrap_or_default();
+                    let progress = m
+                        .resume_position()
+                        .map(|position| Self::progress(position, m.effective_length()))
+                        .unwrap_or_default();
+                    let rating = m
+                        .rating()
+                        .map(|rating| format!("\nRATING: {}", movie::pretty_rating(rating)))
+                        .unwrap_or_default();
+                    let tags = match m.pretty_tags() {
+                        tags if tags.is_empty() => tags,
+                        tags => format!("\nTAGS: {tags}"),
+                    };
+                    let sessions = Self::sessions(m.sessions());
+                    let queued = m
+                        .paths()
+                        .next()
+                        .is_some_and(|path| archive.transcode_queue().contains(path));
+                    let converted = match (queued, m.transcoded()) {
+                        (true, _) => "\nCONVERTED: QUEUED",
+                        (false, Some(_)) => "\nCONVERTED: YES",
+                        (false, None) => "",
+                    };
+                    let ends = Self::ends(budget, m.remaining_length());
                     format!(
-                        "WATCHED: {}\nLENGTH: {}",
+                        "WATCHED: {}{progress}{rating}{sessions}{tags}\nLENGTH: {}{ends}{credits}{parts}{quality}{versions}{integrity}{converted}",
                         m.pretty_since_watched(),
                         m.pretty_length()
                     )
-                })
+                }),
+                Item::Series(name) => archive.get_series(&name).map(|s| {
+                    format!(
+                        "EPISODES: {}/{}\nSEASONS: {}\nNEXT: {}",
+                        s.num_watched(),
+                        s.num_episodes(),
+                        s.seasons().len(),
+                        s.next_episode()
+                            .map_or_else(|| "-".to_string(), Episode::code)
+                    )
+                }),
+                Item::Episode(series, path) => archive
+                    .get_series(&series)
+                    .and_then(|s| s.episodes().find(|e| e.path() == path))
+                    .map(|e| {
+                        let progress = e
+                            .resume_position()
+                            .map(|position| Self::progress(position, e.length()))
+                            .unwrap_or_default();
+                        let sessions = Self::sessions(e.sessions());
+                        let ends = Self::ends(budget, e.remaining_length());
+                        format!(
+                            "WATCHED: {}{progress}{sessions}\nLENGTH: {}{ends}",
+                            e.pretty_since_watched(),
+                            e.pretty_length()
+                        )
+                    }),
+            }
         });
 
-        if let Some(content) = movie_data {
+        if let Some(content) = data {
             siv.call_on_name(INFO_ID, |v: &mut TextView| {
                 v.set_content(content);
             });
         }
+        PosterView::refresh(siv);
+    }
+
+    /// In budget mode, when something would end if started now.
+    fn ends(budget: Option<TimeBudget>, length: u64) -> String {
+        budget
+            .map(|_| {
+                let settings = &Config::get().time_budget;
+                let end = budget::pretty_end(length, settings, TimeBudget::now());
+                format!("\nENDS: {end}")
+            })
+            .unwrap_or_default()
+    }
+
+    /// Number of times something was played and for how long, if ever.
+    fn sessions(sessions: &[Session]) -> String {
+        if sessions.is_empty() {
+            return String::new();
+        }
+        format!("\nSESSIONS: {}", session::pretty_sessions(sessions))
+    }
+
+    /// Progress bar and position of a playback stopped before the end, each
+    /// on its own line to fit the panel.
+    fn progress(position: u64, length: u64) -> String {
+        format!(
+            "\n{}\nSTOPPED: {}",
+            movie::pretty_progress(position, length),
+            movie::pretty_position(position)
+        )
     }
 }
@@ -0,0 +1,570 @@
+use std::{
+    path::{Path, PathBuf},
+    thread,
+    time::SystemTime,
+};
+
+use cursive::{Cursive, views::Dialog};
+
+use crate::{
+    archive::Archive,
+    artwork::{Artwork, Image},
+    config::Config,
+    contact_sheet::ContactSheet,
+    credits::Credits,
+    movie::Movie,
+    player::{PlayRequest, Playback, Player},
+    series::Episode,
+    session::Session,
+    subtitles::Subtitles,
+    tracks::Tracks,
+    transcoder::Transcoder,
+    tui::{
+        contact_sheet_view::ContactSheetView,
+        filter_view::FilterView,
+        info_view::InfoView,
+        list_view::{Item, ListView},
+        playback_view::PlaybackView,
+        poster_view::{ImageKey, Poster},
+        subtitles_view::SubtitlesView,
+        tracks_view::TracksView,
+        user_data::UserData,
+    },
+    verifier::Verifier,
+};
+
+const VERIFY_JOB: &str = "verify";
+const ARTWORK_JOB: &str = "artwork";
+const CONTACT_SHEET_JOB: &str = "contact sheet";
+const SUBTITLES_JOB: &str = "subtitles";
+const SUBTITLE_TRACKS_JOB: &str = "subtitle tracks";
+const TRACKS_JOB: &str = "tracks";
+const TRANSCODE_JOB: &str = "transcode";
+const CREDITS_JOB: &str = "credits";
+const PLAYBACK_JOB: &str = "playback";
+
+/// Seconds of playback between two saves of the resume position.
+const RESUME_SAVE_INTERVAL: u64 = 30;
+/// Shortest session recorded, and asked about when the playback was not
+/// followed. Launchers such as xdg-open exit as soon as the actual player is
+/// started.
+const MIN_SESSION: u64 = 60;
+
+/// Long running tasks executed in a background thread, reporting their
+/// progress next to the filter.
+pub struct Jobs;
+
+impl Jobs {
+    /// Checks the given movies for corruption, one after another.
+    pub fn verify(siv: &mut Cursive, names: &[String]) {
+        let Some(user_data) = siv.user_data::<UserData>() else {
+            return;
+        };
+        if !user_data.start_job(VERIFY_JOB) {
+            return;
+        }
+
+        let archive = user_data.archive();
+        let movies: Vec<(String, Vec<PathBuf>)> = names
+            .iter()
+            .map(|name| archive.get_movie(name))
+            .filter(|movie| !movie.is_disc())
+            .map(|movie| (movie.name().to_owned(), movie.paths().cloned().collect()))
+            .collect();
+
+        let cb = siv.cb_sink().clone();
+        thread::spawn(move || {
+            let total = movies.len();
+
+            for (i, (name, paths)) in movies.into_iter().enumerate() {
+                let progress = format!("Verifying {}/{total}: {name}", i + 1);
+                cb.send(Box::new(move |siv| {
+                    Self::set_progress(siv, VERIFY_JOB, progress);
+                }))
+                .ok();
+
+                let integrity = Verifier::verify(paths.iter().map(PathBuf::as_path));
+
+                cb.send(Box::new(move |siv| {
+                    siv.with_user_data(|user_data: &mut UserData| {
+                        let archive = user_data.archive_mut();
+                        archive.set_integrity(&name, integrity);
+                        archive.save().ok();
+                    });
+                    ListView::refresh(siv);
+                }))
+                .ok();
+            }
+
+            cb.send(Box::new(|siv| Self::finish(siv, VERIFY_JOB))).ok();
+        });
+    }
+
+    /// Looks for the start of the end credits of the given movies, one after
+    /// another, listing the ones that failed at the end.
+    pub fn credits(siv: &mut Cursive, names: &[String]) {
+        let Some(user_data) = siv.user_data::<UserData>() else {
+            return;
+        };
+        if !user_data.start_job(CREDITS_JOB) {
+            return;
+        }
+
+        let archive = user_data.archive();
+        let movies: Vec<Movie> = names
+            .iter()
+            .map(|name| archive.get_movie(name))
+            .filter(|movie| !movie.is_disc())
+            .cloned()
+            .collect();
+
+        let cb = siv.cb_sink().clone();
+        thread::spawn(move || {
+            let total = movies.len();
+            let mut failures = Vec::new();
+
+            for (i, movie) in movies.into_iter().enumerate() {
+                let progress = format!("Credits {}/{total}: {}", i + 1, movie.name());
+                cb.send(Box::new(move |siv| {
+                    Self::set_progress(siv, CREDITS_JOB, progress);
+                }))
+                .ok();
+
+                let credits_start = match Credits::detect(&movie) {
+                    Ok(credits_start) => credits_start,
+                    Err(e) => {
+                        failures.push(format!("{}: {e}", movie.name()));
+                        continue;
+                    }
+                };
+                let name = movie.name().to_owned();
+                cb.send(Box::new(move |siv| {
+                    siv.with_user_data(|user_data: &mut UserData| {
+                        let archive = user_data.archive_mut();
+                        archive.set_credits_start(&name, credits_start);
+                        archive.save().ok();
+                    });
+                    InfoView::refresh(siv);
+                }))
+                .ok();
+            }
+
+            cb.send(Box::new(move |siv| {
+                Self::finish(siv, CREDITS_JOB);
+                if !failures.is_empty() {
+                    siv.add_layer(Dialog::info(format!(
+                        "Credits could not be detected in:\n{}",
+                        failures.join("\n")
+                    )));
+                }
+            }))
+            .ok();
+        });
+    }
+
+    /// Extracts a thumbnail for every movie that has neither a poster nor a
+    /// cached one.
+    pub fn artwork(siv: &mut Cursive) {
+        let Some(user_data) = siv.user_data::<UserData>() else {
+            return;
+        };
+        if !user_data.start_job(ARTWORK_JOB) {
+            return;
+        }
+
+        let movies: Vec<Movie> = user_data.archive().movies.clone();
+
+        let cb = siv.cb_sink().clone();
+        thread::spawn(move || {
+            let missing: Vec<Movie> = movies.into_iter().filter(Artwork::is_missing).collect();
+            let total = missing.len();
+
+            for (i, movie) in missing.iter().enumerate() {
+                let progress = format!("Thumbnails {}/{total}", i + 1);
+                cb.send(Box::new(move |siv| {
+                    Self::set_progress(siv, ARTWORK_JOB, progress);
+                }))
+                .ok();
+
+                if Artwork::extract(movie).is_ok() {
+                    cb.send(Box::new(InfoView::refresh)).ok();
+                }
+            }
+
+            cb.send(Box::new(|siv| Self::finish(siv, ARTWORK_JOB))).ok();
+        });
+    }
+
+    /// Loads an image scaled to the size of a view, and hands it to the view
+    /// named `view` once done.
+    pub fn image(siv: &Cursive, view: &'static str, key: ImageKey) {
+        let cb = siv.cb_sink().clone();
+        thread::spawn(move || {
+            let (path, width, height) = &key;
+            let image = Image::load(path, *width, *height).ok();
+
+            cb.send(Box::new(move |siv| {
+                siv.call_on_name(view, |poster: &mut Poster| poster.add_image(key, image));
+            }))
+            .ok();
+        });
+    }
+
+    /// Generates the contact sheet of a movie and opens it once done.
+    pub fn contact_sheet(siv: &mut Cursive, movie: Movie) {
+        let Some(user_data) = siv.user_data::<UserData>() else {
+            return;
+        };
+        if !user_data.start_job(CONTACT_SHEET_JOB) {
+            return;
+        }
+        let progress = format!("Contact sheet: {}", movie.name());
+        Self::set_progress(siv, CONTACT_SHEET_JOB, progress);
+
+        let cb = siv.cb_sink().clone();
+        thread::spawn(move || {
+            let result = ContactSheet::generate(&movie).map_err(|e| e.to_string());
+
+            cb.send(Box::new(move |siv| {
+                Self::finish(siv, CONTACT_SHEET_JOB);
+                match result {
+                    Ok(path) => ContactSheetView::open(siv, path),
+                    Err(e) => siv.add_layer(Dialog::info(format!("Contact sheet failed: {e}"))),
+                }
+            }))
+            .ok();
+        });
+    }
+
+    /// Reads the subtitle streams of the first file of a movie, and lets the
+    /// user pick the ones to extract once done.
+    pub fn subtitle_tracks(siv: &mut Cursive, paths: Vec<PathBuf>) {
+        let Some(user_data) = siv.user_data::<UserData>() else {
+            return;
+        };
+        if !user_data.start_job(SUBTITLE_TRACKS_JOB) {
+            return;
+        }
+        Self::set_progress(siv, SUBTITLE_TRACKS_JOB, "Reading subtitles".to_string());
+
+        let cb = siv.cb_sink().clone();
+        thread::spawn(move || {
+            let tracks = paths
+                .first()
+                .map_or_else(|| Ok(Vec::new()), |path| Subtitles::tracks(path))
+                .map_err(|e| e.to_string());
+
+            cb.send(Box::new(move |siv| {
+                Self::finish(siv, SUBTITLE_TRACKS_JOB);
+                SubtitlesView::open(siv, paths, tracks);
+            }))
+            .ok();
+        });
+    }
+
+    /// Reads the audio and subtitle streams of a movie file, and lets the
+    /// user pick the ones to play once done.
+    pub fn tracks(siv: &mut Cursive, name: String, path: PathBuf) {
+        let Some(user_data) = siv.user_data::<UserData>() else {
+            return;
+        };
+        if !user_data.start_job(TRACKS_JOB) {
+            return;
+        }
+        Self::set_progress(siv, TRACKS_JOB, "Reading tracks".to_string());
+
+        let cb = siv.cb_sink().clone();
+        thread::spawn(move || {
+            let tracks = Tracks::probe(&path).map_err(|e| e.to_string());
+
+            cb.send(Box::new(move |siv| {
+                Self::finish(siv, TRACKS_JOB);
+                TracksView::open(siv, name, tracks);
+            }))
+            .ok();
+        });
+    }
+
+    /// Extracts the subtitle streams with the given indices from each file
+    /// of a movie.
+    pub fn subtitles(siv: &mut Cursive, paths: Vec<PathBuf>, indices: Vec<usize>) {
+        let Some(user_data) = siv.user_data::<UserData>() else {
+            return;
+        };
+        if !user_data.start_job(SUBTITLES_JOB) {
+            return;
+        }
+        Self::set_progress(siv, SUBTITLES_JOB, "Extracting subtitles".to_string());
+
+        let cb = siv.cb_sink().clone();
+        thread::spawn(move || {
+            let result = paths.iter().try_fold(0, |written, path| {
+                let tracks: Vec<_> = Subtitles::tracks(path)?
+                    .into_iter()
+                    .filter(|track| indices.contains(&track.index()))
+                    .collect();
+                Subtitles::extract(path, &tracks).map(|sidecars| written + sidecars.len())
+            });
+            let message = match result {
+                Ok(written) => format!("Wrote {written} subtitle files"),
+                Err(e) => format!("Subtitle extraction failed: {e}"),
+            };
+
+            cb.send(Box::new(move |siv| {
+                Self::finish(siv, SUBTITLES_JOB);
+                siv.add_layer(Dialog::info(message));
+            }))
+            .ok();
+        });
+    }
+
+    /// Converts the first file of the queue, then moves on to the next one
+    /// until the queue is empty.
+    pub fn transcode(siv: &mut Cursive) {
+        let Some(user_data) = siv.user_data::<UserData>() else {
+            return;
+        };
+        let Some(path) = user_data.archive().transcode_queue().first().cloned() else {
+            return;
+        };
+        if !user_data.start_job(TRANSCODE_JOB) {
+            return;
+        }
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Self::set_progress(siv, TRANSCODE_JOB, format!("Converting: {name}"));
+
+        let cb = siv.cb_sink().clone();
+        thread::spawn(move || {
+            let result = Transcoder::convert(&path, &Config::get().transcode, |percent| {
+                let progress = format!("Converting {percent}%: {name}");
+                cb.send(Box::new(move |siv| {
+                    Self::set_progress(siv, TRANSCODE_JOB, progress);
+                }))
+                .ok();
+            })
+            .map_err(|e| e.to_string());
+
+            cb.send(Box::new(move |siv| {
+                siv.with_user_data(|user_data: &mut UserData| {
+                    let archive = user_data.archive_mut();
+                    archive.finish_transcode(&path, result.clone().ok().flatten());
+                    archive.save().ok();
+                });
+                Self::finish(siv, TRANSCODE_JOB);
+                match result {
+                    Ok(Some(_)) => {}
+                    Ok(None) => siv.add_layer(Dialog::info(format!(
+                        "{name} is already compatible with the profile, so it was skipped"
+                    ))),
+                    Err(e) => siv.add_layer(Dialog::info(format!("Converting {name} failed: {e}"))),
+                }
+                InfoView::refresh(siv);
+                Self::transcode(siv);
+            }))
+            .ok();
+        });
+    }
+
+    /// Whether a player started by `playback` is still open.
+    pub fn is_playing(siv: &mut Cursive) -> bool {
+        siv.user_data::<UserData>()
+            .is_some_and(|user_data| user_data.is_running(PLAYBACK_JOB))
+    }
+
+    /// Starts the player and waits for the playback of a movie or episode to
+    /// end, recording the session. Backends that follow the position remember
+    /// where it was stopped and mark it as watched once enough of it was
+    /// played; with the others the user is asked.
+    pub fn playback(siv: &mut Cursive, item: Item, request: PlayRequest) {
+        let Some(user_data) = siv.user_data::<UserData>() else {
+            return;
+        };
+        let (name, length) = match &item {
+            Item::Movie(name) => {
+                let movie = user_data.archive().get_movie(name);
+                (name.clone(), movie.effective_length())
+            }
+            Item::Episode(series, path) => {
+                let episode = user_data
+                    .archive()
+                    .get_series(series)
+                    .and_then(|series| series.episodes().find(|episode| episode.path() == path));
+                let name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                (name, episode.map_or(0, Episode::length))
+            }
+            Item::Series(_) => return,
+        };
+        if !user_data.start_job(PLAYBACK_JOB) {
+            return;
+        }
+        Self::set_progress(siv, PLAYBACK_JOB, format!("Playing: {name}"));
+        let start = SystemTime::now();
+
+        let cb = siv.cb_sink().clone();
+        thread::spawn(move || {
+            // Remote players are reached over the network, which may take a
+            // while
+            let playing = Player::start(&request);
+            let launcher = playing
+                .as_ref()
+                .ok()
+                .and_then(|playing| playing.launcher().map(str::to_owned));
+            let mut saved = 0;
+            let mut progress = |position: u64| {
+                let progress = format!("Playing {}%: {name}", position * 100 / length.max(1));
+                let save = position.abs_diff(saved) >= RESUME_SAVE_INTERVAL;
+                if save {
+                    saved = position;
+                }
+                let item = item.clone();
+                cb.send(Box::new(move |siv| {
+                    Self::set_progress(siv, PLAYBACK_JOB, progress);
+                    if save {
+                        siv.with_user_data(|user_data: &mut UserData| {
+                            let archive = user_data.archive_mut();
+                            Self::set_resume_position(archive, &item, Some(position));
+                            archive.save().ok();
+                        });
+                    }
+                }))
+                .ok();
+            };
+            let result = playing
+                .and_then(|playing| playing.wait(&mut progress))
+                .map_err(|e| e.to_string());
+            let session = Session::new(start, SystemTime::now());
+
+            cb.send(Box::new(move |siv| {
+                Self::finish(siv, PLAYBACK_JOB);
+                let completed =
+                    Self::end_playback(siv, item, session, result, length, launcher.as_deref());
+                if let Some(completed) = completed {
+                    Self::continue_queue(siv, completed);
+                }
+            }))
+            .ok();
+        });
+    }
+
+    /// Records a finished playback and what was seen of it, returning true
+    /// only if it was followed and enough of it was played, or None when the
+    /// user is asked whether it was watched.
+    fn end_playback(
+        siv: &mut Cursive,
+        item: Item,
+        session: Session,
+        result: Result<Option<Playback>, String>,
+        length: u64,
+        launcher: Option<&str>,
+    ) -> Option<bool> {
+        let watched = match &result {
+            Ok(Some(playback)) => {
+                playback.finished || Config::get().player.is_watched(playback.position, length)
+            }
+            _ => false,
+        };
+
+        siv.with_user_data(|user_data: &mut UserData| {
+            let archive = user_data.archive_mut();
+            if session.duration() >= MIN_SESSION {
+                match &item {
+                    Item::Movie(name) => archive.add_session(name, session),
+                    Item::Episode(series, path) => {
+                        archive.add_episode_session(series, path, session);
+                    }
+                    Item::Series(_) => {}
+                }
+            }
+            if let Ok(Some(playback)) = &result {
+                match &item {
+                    Item::Movie(name) if watched => archive.set_watched(name),
+                    Item::Episode(series, path) if watched => {
+                        archive.set_episode_watched(series, path);
+                    }
+                    _ => {
+                        let position = Some(playback.position).filter(|&position| position > 0);
+                        Self::set_resume_position(archive, &item, position);
+                    }
+                }
+            }
+            archive.save().ok();
+        });
+        ListView::refresh(siv);
+
+        match result {
+            Ok(Some(_)) if watched => PlaybackView::prompt(siv, item, false),
+            Ok(Some(_)) => return Some(false),
+            // Too short for anything to have been watched
+            Ok(None) if launcher.is_some() || session.duration() < MIN_SESSION => {
+                let reason = launcher.map_or_else(
+                    || "The player was closed straight away".to_owned(),
+                    |launcher| format!("{launcher} hands movies over to another player and exits"),
+                );
+                siv.add_layer(Dialog::info(format!(
+                    "{reason}, so this playback was not recorded. Mark the movie as watched \
+                     with 'w', or set player.command to the player itself."
+                )));
+                return Some(false);
+            }
+            // Only the user knows whether it was watched, and so whether the
+            // queue goes on
+            Ok(None) => {
+                PlaybackView::prompt(siv, item, true);
+                return None;
+            }
+            Err(e) => {
+                siv.add_layer(Dialog::info(format!("Playback failed: {e}")));
+                return Some(false);
+            }
+        }
+        Some(true)
+    }
+
+    /// Moves on to the next entry of the play queue when it is being played
+    /// through, or stops there if the last one was not followed to the end.
+    pub fn continue_queue(siv: &mut Cursive, completed: bool) {
+        let Some(queued) = siv
+            .user_data::<UserData>()
+            .and_then(|user_data| user_data.queued().map(Path::to_path_buf))
+        else {
+            return;
+        };
+        if !completed {
+            siv.with_user_data(|user_data: &mut UserData| user_data.set_queued(None));
+            return;
+        }
+
+        siv.with_user_data(|user_data: &mut UserData| {
+            let archive = user_data.archive_mut();
+            archive.dequeue(&queued);
+            archive.save().ok();
+        });
+        ListView::play_queued(siv);
+    }
+
+    fn set_resume_position(archive: &mut Archive, item: &Item, position: Option<u64>) {
+        match item {
+            Item::Movie(name) => archive.set_resume_position(name, position),
+            Item::Episode(series, path) => {
+                archive.set_episode_resume_position(series, path, position);
+            }
+            Item::Series(_) => {}
+        }
+    }
+
+    fn set_progress(siv: &mut Cursive, job: &'static str, progress: String) {
+        siv.with_user_data(|user_data: &mut UserData| user_data.set_progress(job, progress));
+        FilterView::refresh(siv);
+    }
+
+    fn finish(siv: &mut Cursive, job: &'static str) {
+        siv.with_user_data(|user_data: &mut UserData| user_data.finish_job(job));
+        FilterView::refresh(siv);
+    }
+}
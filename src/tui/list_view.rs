@@ -1,162 +1,913 @@
 use std::{
-    path::Path,
-    process::{Command, Stdio},
+    path::{Path, PathBuf},
     thread,
+    time::{SystemTime, UNIX_EPOCH},
 };
 
 use cursive::{
-    Cursive,
+    CbSink, Cursive, Vec2, View,
+    event::{Event, EventResult, EventTrigger, MouseButton, MouseEvent},
+    style::{BaseColor, Effect, Style},
+    utils::markup::StyledString,
     view::{Nameable, Resizable, Scrollable, ViewWrapper},
-    views::{NamedView, Panel, ResizedView, ScrollView, SelectView},
+    views::{
+        Dialog, HideableView, LinearLayout, NamedView, OnEventView, Panel, ResizedView, ScrollView,
+        SelectView, TextView,
+    },
 };
+use time::OffsetDateTime;
+use unicode_width::UnicodeWidthStr;
 
 use crate::{
+    archive::Archive,
+    budget::{self, TimeBudget},
     collector::Collector,
-    movie::Movie,
-    tui::{filter_view::Filter, info_view::InfoView, stats_view::StatsView, user_data::UserData},
+    config::Config,
+    fuzzy,
+    movie::{Movie, Resolution, pretty_length, pretty_rating, pretty_since_watched, pretty_size},
+    player::PlayRequest,
+    query::Entry,
+    series::{Episode, Series},
+    sort::{self, SortKey, SortOrder},
+    table::{self, Column},
+    tracks::TrackChoices,
+    tui::{
+        filter_view::FilterView, info_view::InfoView, jobs::Jobs, stats_view::StatsView,
+        user_data::UserData,
+    },
+    watch_later::WatchLater,
 };
 
 pub const SELECT_ID: &str = "select";
 pub const SCROLL_ID: &str = "scroll";
+const HEADER_ID: &str = "header";
 
-type ViewType = Panel<ResizedView<NamedView<ScrollView<NamedView<SelectView<String>>>>>>;
+/// What a row of the list refers to.
+#[derive(Clone, PartialEq, Eq)]
+pub enum Item {
+    Movie(String),
+    Series(String),
+    /// An episode, identified by its series name and file path
+    Episode(String, PathBuf),
+}
+
+impl Item {
+    /// Resolves a series to its next episode, leaving other items untouched.
+    fn resolve(self, archive: &Archive) -> Option<Self> {
+        match self {
+            Self::Series(name) => archive
+                .get_series(&name)
+                .and_then(Series::next_episode)
+                .map(|episode| Self::Episode(name, episode.path().to_path_buf())),
+            item => Some(item),
+        }
+    }
+
+    /// Finds the movie or episode a file of the play queue belongs to.
+    pub fn from_queued(archive: &Archive, path: &Path) -> Option<Self> {
+        let movie = archive
+            .movies
+            .iter()
+            .find(|movie| movie.version_paths().any(|version| version == path))
+            .map(|movie| Self::Movie(movie.name().to_owned()));
+        let episode = || {
+            archive
+                .series
+                .iter()
+                .find(|series| series.episodes().any(|episode| episode.path() == path))
+                .map(|series| Self::Episode(series.name().to_owned(), path.to_path_buf()))
+        };
+
+        movie.or_else(episode)
+    }
+
+    /// File the item is kept as in the play queue, series being resolved to
+    /// their next episode.
+    fn queue_path(self, archive: &Archive) -> Option<PathBuf> {
+        match self.resolve(archive)? {
+            Self::Movie(name) => archive.get_movie(&name).paths().next().cloned(),
+            Self::Episode(_, path) => Some(path),
+            Self::Series(_) => None,
+        }
+    }
+}
+
+/// Rows of the list with the item each one refers to.
+type Items = Vec<(StyledString, Item)>;
+
+/// A top level entry of the list, before it is turned into items.
+#[derive(Clone, Copy)]
+enum Row<'a> {
+    Movie(&'a Movie),
+    Series(&'a Series),
+}
+
+impl Row<'_> {
+    fn name(&self) -> &str {
+        match self {
+            Self::Movie(movie) => movie.name(),
+            Self::Series(series) => series.name(),
+        }
+    }
+
+    fn since_watched(&self) -> Option<SystemTime> {
+        match self {
+            Self::Movie(movie) => movie.since_watched(),
+            Self::Series(series) => series.since_watched(),
+        }
+    }
+
+    fn is_in_progress(&self) -> bool {
+        match self {
+            Self::Movie(movie) => movie.is_in_progress(),
+            Self::Series(series) => series.is_in_progress(),
+        }
+    }
+
+    const fn entry(&self) -> Entry<'_> {
+        match *self {
+            Self::Movie(movie) => Entry::Movie(movie),
+            Self::Series(series) => Entry::Series(series),
+        }
+    }
+
+    /// Value compared when sorting by a key other than the title. Series only
+    /// have a length and the time they were last watched.
+    fn sort_value(&self, key: SortKey) -> Option<u64> {
+        let seconds = |time: SystemTime| {
+            time.duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs())
+        };
+        match (self, key) {
+            (Self::Movie(movie), SortKey::Length) => Some(movie.effective_length()),
+            (Self::Series(series), SortKey::Length) => series.next_episode().map(Episode::length),
+            (_, SortKey::LastWatched) => self.since_watched().map(seconds),
+            (Self::Movie(movie), SortKey::Added) => movie.added().map(seconds),
+            (Self::Movie(movie), SortKey::Size) => Some(movie.size()),
+            (Self::Movie(movie), SortKey::Resolution) => movie.resolution().map(Resolution::pixels),
+            (Self::Movie(movie), SortKey::Year) => movie.year().map(u64::from),
+            (Self::Movie(movie), SortKey::Rating) => movie.rating().map(u64::from),
+            _ => None,
+        }
+    }
+
+    /// Text of a table column other than the title.
+    fn cell(&self, column: Column) -> String {
+        let value = self.sort_value(column.sort_key());
+        match (self, column) {
+            (_, Column::Year) => value.map(|year| year.to_string()),
+            (_, Column::Length) => value.map(pretty_length),
+            (Self::Movie(movie), Column::Resolution) => movie.resolution().map(Resolution::pretty),
+            (_, Column::LastWatched) => Some(pretty_since_watched(self.since_watched())),
+            (Self::Movie(movie), Column::Rating) => movie.rating().map(pretty_rating),
+            (_, Column::Size) => value.map(pretty_size),
+            _ => None,
+        }
+        .unwrap_or_default()
+    }
+
+    /// Seconds left to watch of a movie or of the next episode of a series.
+    fn remaining_length(&self) -> Option<u64> {
+        match self {
+            Self::Movie(movie) => Some(movie.remaining_length()),
+            Self::Series(series) => series.next_episode().map(Episode::remaining_length),
+        }
+    }
+
+    /// Whether the row is shown with the filter, query, search and time
+    /// budget of `user_data`, the budget being checked from `now`.
+    fn is_shown(&self, user_data: &UserData, now: OffsetDateTime) -> bool {
+        let budget_settings = &Config::get().time_budget;
+        user_data
+            .filter()
+            .matches(self.since_watched(), self.is_in_progress())
+            && user_data
+                .query()
+                .is_none_or(|query| query.matches(self.entry()))
+            && (!user_data.is_searching() || fuzzy::find(user_data.search(), self.name()).is_some())
+            && user_data.budget().is_none_or(|budget| {
+                self.remaining_length()
+                    .is_some_and(|length| budget.fits(length, budget_settings, now))
+            })
+    }
+}
+
+type ViewType = Panel<ResizedView<OnEventView<LinearLayout>>>;
 
 pub struct ListView {
     view: ViewType,
+    /// Width rows were last laid out for
+    width: usize,
+    cb_sink: CbSink,
 }
 
 impl ViewWrapper for ListView {
     cursive::wrap_impl!(self.view: ViewType);
+
+    fn wrap_layout(&mut self, size: Vec2) {
+        // Borders of the panel and the scrollbar
+        let width = size.x.saturating_sub(4);
+        if width != self.width {
+            self.width = width;
+            self.cb_sink
+                .send(Box::new(move |siv| {
+                    let is_table = siv.user_data::<UserData>().is_some_and(|user_data| {
+                        user_data.set_list_width(width);
+                        user_data.is_table()
+                    });
+                    if is_table {
+                        Self::refresh(siv);
+                    }
+                }))
+                .ok();
+        }
+        self.view.layout(size);
+    }
 }
 
 impl ListView {
     pub fn new(siv: &Cursive, path: &Path) -> Self {
-        let view = SelectView::<String>::new()
+        let list = SelectView::<Item>::new()
             .on_select(|siv, _| InfoView::refresh(siv))
+            .on_submit(Self::toggle_expanded)
             .with_name(SELECT_ID)
             .scrollable()
             .scroll_x(true)
-            .with_name(SCROLL_ID)
+            .with_name(SCROLL_ID);
+        let header = HideableView::new(TextView::new("")).with_name(HEADER_ID);
+
+        let click = EventTrigger::from_fn(|event| {
+            matches!(
+                event,
+                Event::Mouse {
+                    event: MouseEvent::Press(MouseButton::Left),
+                    ..
+                }
+            )
+        });
+        let view = OnEventView::new(LinearLayout::vertical().child(header).child(list))
+            .on_pre_event_inner(click, |layout, event| {
+                let Event::Mouse {
+                    offset, position, ..
+                } = *event
+                else {
+                    return None;
+                };
+                let position = position.checked_sub(offset)?;
+                let header = layout
+                    .get_child_mut(0)?
+                    .downcast_mut::<NamedView<HideableView<TextView>>>()?;
+                // The header is only shown in table mode
+                (position.y == 0 && header.get_mut().is_visible())
+                    .then(|| EventResult::with_cb(move |siv| Self::sort_by_header(siv, position.x)))
+            })
             .full_screen();
 
         Self::background_refresh(siv, path);
 
         Self {
             view: Panel::new(view),
+            width: 0,
+            cb_sink: siv.cb_sink().clone(),
         }
     }
 
     pub fn refresh(siv: &mut Cursive) {
         let items = Self::get_items(siv);
+        Self::refresh_header(siv);
 
-        if let Some(mut view) = siv.find_name::<SelectView<String>>(SELECT_ID) {
+        if let Some(mut view) = siv.find_name::<SelectView<Item>>(SELECT_ID) {
             let selected_id = view.selected_id();
+            let selected = view.selection();
 
             view.clear();
 
             view.add_all(items);
 
-            if let Some(id) = selected_id {
-                view.set_selection(id);
+            // The selected entry may have moved, e.g. when filtering
+            let index = selected
+                .and_then(|selected| view.iter().position(|(_, item)| *item == *selected))
+                .or(selected_id);
+            if let Some(index) = index {
+                view.set_selection(index);
             }
         }
         InfoView::refresh(siv);
         StatsView::refresh(siv);
     }
 
+    /// Switches between a table and a list of titles.
+    pub fn toggle_table(siv: &mut Cursive) {
+        siv.with_user_data(UserData::toggle_table);
+
+        Self::refresh(siv);
+    }
+
+    /// Shows the headers of the table columns, or hides them when the list
+    /// only has titles.
+    fn refresh_header(siv: &mut Cursive) {
+        let Some(header) = siv.user_data::<UserData>().map(|user_data| {
+            let order = user_data.archive().sort();
+            let mut header = StyledString::new();
+            for (i, &(column, width)) in user_data.columns().iter().enumerate() {
+                if i > 0 {
+                    header.append_plain(table::SEPARATOR);
+                }
+                header.append_styled(column.label(order, width), Effect::Bold);
+            }
+            header
+        }) else {
+            return;
+        };
+
+        siv.call_on_name(HEADER_ID, |view: &mut HideableView<TextView>| {
+            view.set_visible(!header.is_empty());
+            view.get_inner_mut().set_content(header);
+        });
+    }
+
+    /// Sorts the list by the column whose header is at `x`.
+    fn sort_by_header(siv: &mut Cursive, x: usize) {
+        let Some(column) = siv.user_data::<UserData>().and_then(|user_data| {
+            let mut end = 0;
+            user_data.columns().iter().find_map(|&(column, width)| {
+                end += width + table::SEPARATOR.len();
+                (x < end).then_some(column)
+            })
+        }) else {
+            return;
+        };
+
+        FilterView::sort_by(siv, column.sort_key());
+    }
+
     pub fn toggle_watched(siv: &mut Cursive) {
-        let name = Self::get_selected_name(siv);
+        let Some(item) = Self::get_selected(siv) else {
+            return;
+        };
         siv.with_user_data(|user_data: &mut UserData| {
             let archive = user_data.archive_mut();
-            archive.toggle_watched(&name);
+            match item {
+                Item::Movie(name) => archive.toggle_watched(&name),
+                Item::Episode(series, path) => archive.toggle_episode_watched(&series, &path),
+                // Watching a series means moving on to its next episode
+                Item::Series(_) => {
+                    if let Some(Item::Episode(series, path)) = item.resolve(archive) {
+                        archive.set_episode_watched(&series, &path);
+                    }
+                }
+            }
             archive.save().ok();
         });
 
         Self::refresh(siv);
     }
 
-    pub fn play_movie(siv: &mut Cursive) {
-        let name = Self::get_selected_name(siv);
+    pub fn cycle_version(siv: &mut Cursive) {
+        let Some(Item::Movie(name)) = Self::get_selected(siv) else {
+            return;
+        };
         siv.with_user_data(|user_data: &mut UserData| {
             let archive = user_data.archive_mut();
-            let path = archive.get_path(&name);
-            let path_string = path.to_string_lossy().into_owned();
-
-            Command::new("xdg-open")
-                .arg(path_string)
-                .stderr(Stdio::null())
-                .stdin(Stdio::null())
-                .spawn()
-                .ok();
-
-            archive.set_watched(&name);
+            archive.cycle_version(&name);
             archive.save().ok();
         });
 
         Self::refresh(siv);
     }
 
-    pub fn get_selected_name(siv: &mut Cursive) -> String {
-        siv.call_on_name(SELECT_ID, |s: &mut SelectView<String>| {
-            s.selection().map(|rc_string| rc_string.to_string())
+    pub fn verify_selected(siv: &mut Cursive) {
+        if let Some(Item::Movie(name)) = Self::get_selected(siv) {
+            Jobs::verify(siv, &[name]);
+        }
+    }
+
+    pub fn verify_all(siv: &mut Cursive) {
+        let names: Vec<String> = siv
+            .user_data::<UserData>()
+            .map(|user_data| {
+                let movies = &user_data.archive().movies;
+                movies.iter().map(|movie| movie.name().to_owned()).collect()
+            })
+            .unwrap_or_default();
+
+        Jobs::verify(siv, &names);
+    }
+
+    pub fn detect_credits_selected(siv: &mut Cursive) {
+        if let Some(Item::Movie(name)) = Self::get_selected(siv) {
+            Jobs::credits(siv, &[name]);
+        }
+    }
+
+    /// Looks for the credits of every movie that has not been analysed yet.
+    pub fn detect_credits_all(siv: &mut Cursive) {
+        let names: Vec<String> = siv
+            .user_data::<UserData>()
+            .map(|user_data| {
+                let movies = &user_data.archive().movies;
+                movies
+                    .iter()
+                    .filter(|movie| movie.credits_start().is_none())
+                    .map(|movie| movie.name().to_owned())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Jobs::credits(siv, &names);
+    }
+
+    /// Queues the selected movie for conversion to the transcode profile.
+    pub fn queue_transcode(siv: &mut Cursive) {
+        let Some(Item::Movie(name)) = Self::get_selected(siv) else {
+            return;
+        };
+        let Some(result) = siv.user_data::<UserData>().map(|user_data| {
+            let archive = user_data.archive_mut();
+            let movie = archive.get_movie(&name);
+            if movie.is_disc() || movie.num_parts() > 1 {
+                return Err("Only single file movies can be converted");
+            }
+            if !archive.queue_transcode(&name) {
+                return Err("Already queued for conversion");
+            }
+            archive.save().ok();
+            Ok(())
+        }) else {
+            return;
+        };
+
+        match result {
+            Ok(()) => {
+                InfoView::refresh(siv);
+                Jobs::transcode(siv);
+            }
+            Err(e) => siv.add_layer(Dialog::info(e)),
+        }
+    }
+
+    /// Adds the selected movie or the next episode of the selected series
+    /// to the play queue.
+    pub fn enqueue(siv: &mut Cursive) {
+        let Some(item) = Self::get_selected(siv) else {
+            return;
+        };
+        let queued = siv.user_data::<UserData>().is_some_and(|user_data| {
+            let archive = user_data.archive_mut();
+            let Some(path) = item.queue_path(archive) else {
+                return true;
+            };
+            let queued = archive.enqueue(path);
+            archive.save().ok();
+            queued
+        });
+
+        if !queued {
+            siv.add_layer(Dialog::info("Already in the play queue"));
+        }
+    }
+
+    /// Starts the player on the selected movie or the next episode of the
+    /// selected series, resuming where it was stopped. What was watched is
+    /// recorded once the player is closed.
+    pub fn play_movie(siv: &mut Cursive) {
+        if let Some(item) = Self::get_selected(siv) {
+            Self::play_item(siv, item);
+        }
+    }
+
+    /// Plays an item unless the player is already open.
+    pub fn play_item(siv: &mut Cursive, item: Item) {
+        if Jobs::is_playing(siv) {
+            siv.add_layer(Dialog::info("The player is already open"));
+            return;
+        }
+        Self::play(siv, item);
+    }
+
+    /// Plays the first entry of the play queue that is still in the library,
+    /// the next one being started once the player is closed.
+    pub fn play_queued(siv: &mut Cursive) {
+        let next = siv.user_data::<UserData>().and_then(|user_data| {
+            let archive = user_data.archive();
+            archive
+                .play_queue()
+                .iter()
+                .find_map(|path| Some((path.clone(), Item::from_queued(archive, path)?)))
+        });
+        let started = next.is_some_and(|(path, item)| {
+            siv.with_user_data(|user_data: &mut UserData| user_data.set_queued(Some(path)));
+            Self::play(siv, item)
+        });
+
+        if !started {
+            siv.with_user_data(|user_data: &mut UserData| user_data.set_queued(None));
+        }
+    }
+
+    /// Starts the player on a movie or the next episode of a series in the
+    /// background, returning false if there is nothing to play. Failing to
+    /// start the player is reported once the playback job ends.
+    fn play(siv: &mut Cursive, item: Item) -> bool {
+        let Some(request) = siv.user_data::<UserData>().and_then(|user_data| {
+            let archive = user_data.archive();
+            let item = item.resolve(archive)?;
+            let (path, files, start, tracks, disc) = match &item {
+                Item::Movie(name) => {
+                    let movie = archive.get_movie(name);
+                    // The start offset would apply to every part of a playlist
+                    let start = movie.resume_position().filter(|_| movie.num_parts() == 1);
+                    let files: Vec<PathBuf> = movie.paths().cloned().collect();
+                    let path = movie.play_path();
+                    (path, files, start, movie.tracks(), movie.disc())
+                }
+                Item::Episode(series, path) => {
+                    let start = archive
+                        .get_series(series)
+                        .and_then(|series| series.episodes().find(|e| e.path() == path))
+                        .and_then(Episode::resume_position);
+                    let files = vec![path.clone()];
+                    (
+                        Ok(path.clone()),
+                        files,
+                        start,
+                        TrackChoices::default(),
+                        None,
+                    )
+                }
+                Item::Series(_) => return None,
+            };
+            Some(path.map(|path| {
+                let request = PlayRequest {
+                    path,
+                    files,
+                    start,
+                    tracks,
+                    disc,
+                };
+                (item, request)
+            }))
+        }) else {
+            return false;
+        };
+
+        match request {
+            Ok((item, request)) => {
+                Jobs::playback(siv, item, request);
+                true
+            }
+            Err(e) => {
+                siv.add_layer(Dialog::info(e.to_string()));
+                false
+            }
+        }
+    }
+
+    pub fn get_selected(siv: &mut Cursive) -> Option<Item> {
+        siv.call_on_name(SELECT_ID, |s: &mut SelectView<Item>| {
+            s.selection().map(|item| (*item).clone())
         })
         .flatten()
-        .unwrap_or_default()
+    }
+
+    /// Moves the selection to an item, if it is in the list.
+    pub fn select(siv: &mut Cursive, item: &Item) {
+        let found = siv
+            .call_on_name(SELECT_ID, |s: &mut SelectView<Item>| {
+                let index = s.iter().position(|(_, other)| other == item)?;
+                // The callback only refreshes the info, done below
+                s.set_selection(index);
+                Some(())
+            })
+            .flatten();
+
+        if found.is_some() {
+            InfoView::refresh(siv);
+            siv.call_on_name(
+                SCROLL_ID,
+                |v: &mut ScrollView<NamedView<SelectView<Item>>>| {
+                    v.scroll_to_important_area();
+                },
+            );
+        }
+    }
+
+    /// Moves the selection to the next entry matching the search, or the
+    /// previous one if `forward` is false, wrapping around the list.
+    pub fn jump_to_match(siv: &mut Cursive, forward: bool) {
+        let Some(search) = siv
+            .user_data::<UserData>()
+            .map(|user_data| user_data.search().to_owned())
+            .filter(|search| !search.is_empty())
+        else {
+            return;
+        };
+
+        let found = siv
+            .call_on_name(SELECT_ID, |s: &mut SelectView<Item>| {
+                let matches = |item: &Item| match item {
+                    Item::Movie(name) | Item::Series(name) => fuzzy::find(&search, name).is_some(),
+                    Item::Episode(..) => false,
+                };
+                let len = s.len();
+                let current = s.selected_id().unwrap_or_default();
+                let index = (1..=len)
+                    .map(|step| {
+                        if forward {
+                            (current + step) % len
+                        } else {
+                            (current + len - step) % len
+                        }
+                    })
+                    .find(|&i| s.get_item(i).is_some_and(|(_, item)| matches(item)))?;
+                s.get_item(index).map(|(_, item)| item.clone())
+            })
+            .flatten();
+
+        if let Some(item) = found {
+            Self::select(siv, &item);
+        }
+    }
+
+    fn toggle_expanded(siv: &mut Cursive, item: &Item) {
+        let Item::Series(name) = item else {
+            return;
+        };
+        siv.with_user_data(|user_data: &mut UserData| {
+            let expanded = user_data.expanded_mut();
+            if !expanded.remove(name) {
+                expanded.insert(name.clone());
+            }
+        });
+
+        Self::refresh(siv);
     }
 
     fn background_refresh(siv: &Cursive, path: &Path) {
         let path = path.to_path_buf();
         let cb = siv.cb_sink().clone();
         thread::spawn(move || {
-            let (movies, hash) = Collector::collect(&path);
+            let (movies, series, hash) = Collector::collect(&path);
+            let watch_later = WatchLater::load();
 
             cb.send(Box::new(move |siv| {
                 siv.with_user_data(|user_data: &mut UserData| {
                     let archive = user_data.archive_mut();
-                    archive.update(movies, hash);
+                    archive.update(movies, series, hash);
+                    archive.import_watch_later(&watch_later);
                     archive.save().ok();
                 });
 
                 Self::refresh(siv);
+                Jobs::artwork(siv);
+                Jobs::transcode(siv);
             }))
             .ok();
         });
     }
 
-    fn get_items(siv: &mut Cursive) -> Vec<(String, String)> {
+    /// Whether a movie is shown in the list, as a random pick must be one
+    /// of them.
+    pub fn shows_movie(user_data: &UserData, movie: &Movie, now: OffsetDateTime) -> bool {
+        Row::Movie(movie).is_shown(user_data, now)
+    }
+
+    fn get_items(siv: &mut Cursive) -> Items {
         let Some(user_data) = siv.user_data::<UserData>() else {
             return Vec::new();
         };
-        let filter = *user_data.filter_mut();
-        let archive = &user_data.archive_mut();
+        let search = user_data.search();
+        let budget = user_data.budget();
+        let expanded = user_data.expanded();
+        let archive = user_data.archive();
+        let budget_settings = &Config::get().time_budget;
+        let now = TimeBudget::now();
 
-        let mut filtered_movies: Vec<&Movie> = archive
-            .movies
-            .iter()
-            .filter(|movie| match filter {
-                Filter::NotWatched => movie.since_watched().is_none(),
-                Filter::Watched => movie.since_watched().is_some(),
-                Filter::Empty => true,
+        let movies = archive.movies.iter().map(Row::Movie);
+        let series = archive.series.iter().map(Row::Series);
+
+        let mut filtered: Vec<Row> = movies
+            .chain(series)
+            .filter(|row| row.is_shown(user_data, now))
+            .collect();
+
+        Self::sort(&mut filtered, archive.sort());
+
+        let items: Items = filtered
+            .into_iter()
+            .flat_map(|row| {
+                // In budget mode, entries show when they would end
+                let ends = budget
+                    .and_then(|_| row.remaining_length())
+                    .map(|length| {
+                        format!(
+                            " [ends {}]",
+                            budget::pretty_end(length, budget_settings, now)
+                        )
+                    })
+                    .unwrap_or_default();
+                let name = Self::highlight(row.name(), search);
+                let mut items = match row {
+                    Row::Movie(movie) => {
+                        let mut label =
+                            StyledString::plain(if movie.is_broken() { "! " } else { "" });
+                        label.append(name);
+                        vec![(label, Item::Movie(movie.name().to_string()))]
+                    }
+                    Row::Series(series) => {
+                        Self::series_items(series, name, expanded.contains(series.name()))
+                    }
+                };
+                if let Some((label, _)) = items.first_mut() {
+                    label.append_plain(ends);
+                }
+                items
             })
             .collect();
 
-        filtered_movies.sort_by(|a, b| match (a.since_watched(), b.since_watched()) {
-            (None, None) => a
-                .name()
-                .chars()
-                .map(|c| c.to_ascii_lowercase())
-                .cmp(b.name().chars().map(|c| c.to_ascii_lowercase())),
-            (None, Some(_)) => std::cmp::Ordering::Less,
-            (Some(_), None) => std::cmp::Ordering::Greater,
-            (Some(date_a), Some(date_b)) => date_b.cmp(&date_a),
-        });
+        let (columns, items) = if user_data.is_table() {
+            Self::tabulate(archive, items, user_data.list_width())
+        } else {
+            (Vec::new(), items)
+        };
+        user_data.set_columns(columns);
+        items
+    }
 
-        filtered_movies
+    /// Lays the rows out in the configured columns, as many as fit in
+    /// `width`. Returns the columns shown with their width.
+    fn tabulate(archive: &Archive, items: Items, width: usize) -> (Vec<(Column, usize)>, Items) {
+        let columns = Config::get().table.columns();
+        let cells: Vec<Vec<String>> = items
+            .iter()
+            .map(|(_, item)| {
+                let cell = |column| Self::cell(archive, item, column);
+                columns.iter().copied().map(cell).collect()
+            })
+            .collect();
+        let longest: Vec<usize> = (0..columns.len())
+            .map(|i| {
+                let lengths = cells.iter().map(|row| row[i].width());
+                lengths.max().unwrap_or_default()
+            })
+            .collect();
+        let widths = table::widths(&columns, &longest, width);
+
+        let rows = items
             .into_iter()
-            .map(|item| {
-                let name = item.name().to_string();
-                (name.clone(), name)
+            .zip(cells)
+            .map(|((label, item), cells)| {
+                let mut row = StyledString::new();
+                for (i, (&(column, width), cell)) in widths.iter().zip(&cells).enumerate() {
+                    if i > 0 {
+                        row.append_plain(table::SEPARATOR);
+                    }
+                    if column == Column::Title {
+                        row.append(fit(&label, width));
+                    } else {
+                        row.append_plain(column.cell(cell, width));
+                    }
+                }
+                (row, item)
             })
-            .collect()
+            .collect();
+        (widths, rows)
+    }
+
+    /// Text of a table column for a row of the list, episodes only having
+    /// a length and the time they were watched.
+    fn cell(archive: &Archive, item: &Item, column: Column) -> String {
+        match item {
+            Item::Movie(name) => Row::Movie(archive.get_movie(name)).cell(column),
+            Item::Series(name) => archive
+                .get_series(name)
+                .map(|series| Row::Series(series).cell(column))
+                .unwrap_or_default(),
+            Item::Episode(series, path) => {
+                let episode = archive
+                    .get_series(series)
+                    .and_then(|series| series.episodes().find(|e| e.path() == path));
+                match (episode, column) {
+                    (Some(episode), Column::Length) => pretty_length(episode.length()),
+                    (Some(episode), Column::LastWatched) => {
+                        pretty_since_watched(episode.since_watched())
+                    }
+                    _ => String::new(),
+                }
+            }
+        }
+    }
+
+    fn sort(rows: &mut [Row], order: SortOrder) {
+        let by_title = |a: &Row, b: &Row| sort::natural_cmp(a.name(), b.name());
+        match order.key {
+            SortKey::Unwatched => {
+                rows.sort_by(|a, b| match (a.since_watched(), b.since_watched()) {
+                    (None, None) => a
+                        .name()
+                        .chars()
+                        .map(|c| c.to_ascii_lowercase())
+                        .cmp(b.name().chars().map(|c| c.to_ascii_lowercase())),
+                    (None, Some(_)) => std::cmp::Ordering::Less,
+                    (Some(_), None) => std::cmp::Ordering::Greater,
+                    (Some(date_a), Some(date_b)) => date_b.cmp(&date_a),
+                });
+            }
+            SortKey::Title => rows.sort_by(by_title),
+            key => {
+                // Values such as the year are parsed from the name, so they
+                // are worked out once rather than on every comparison
+                let mut keyed: Vec<(Option<u64>, Row)> =
+                    rows.iter().map(|row| (row.sort_value(key), *row)).collect();
+                keyed.sort_by(|(a_value, a), (b_value, b)| {
+                    order
+                        .compare(*a_value, *b_value)
+                        .then_with(|| by_title(a, b))
+                });
+                for (row, (_, sorted)) in rows.iter_mut().zip(keyed) {
+                    *row = sorted;
+                }
+                return;
+            }
+        }
+        if order.descending {
+            rows.reverse();
+        }
+    }
+
+    /// Emphasizes the characters of a name matched by the search, if any.
+    fn highlight(name: &str, search: &str) -> StyledString {
+        let Some(matched) = fuzzy::find(search, name).filter(|_| !search.is_empty()) else {
+            return StyledString::plain(name);
+        };
+
+        let style = Style::from(BaseColor::Yellow.light()).combine(Effect::Bold);
+        let mut label = StyledString::new();
+        for (i, c) in name.chars().enumerate() {
+            if matched.contains(&i) {
+                label.append_styled(c.to_string(), style);
+            } else {
+                label.append_plain(c.to_string());
+            }
+        }
+        label
+    }
+
+    fn series_items(
+        series: &Series,
+        name_label: StyledString,
+        is_expanded: bool,
+    ) -> Vec<(StyledString, Item)> {
+        let name = series.name();
+        let mut label = StyledString::plain(if is_expanded { "▾ " } else { "▸ " });
+        label.append(name_label);
+        label.append_plain(format!(
+            " [{}/{}]",
+            series.num_watched(),
+            series.num_episodes()
+        ));
+        let mut items = vec![(label, Item::Series(name.to_string()))];
+
+        if is_expanded {
+            let next = series.next_episode().map(Episode::path);
+            items.extend(series.episodes().map(|episode| {
+                let marker = match episode.since_watched() {
+                    Some(_) => "✓",
+                    None if next == Some(episode.path()) => "→",
+                    None => " ",
+                };
+                (
+                    StyledString::plain(format!("  {marker} {}", episode.label())),
+                    Item::Episode(name.to_string(), episode.path().to_path_buf()),
+                )
+            }));
+        }
+
+        items
+    }
+}
+
+/// Cuts a label down to `width` cells of the terminal, ending it with `…`,
+/// or pads it with spaces up to that width.
+fn fit(label: &StyledString, width: usize) -> StyledString {
+    let length: usize = label.spans().map(|span| span.content.width()).sum();
+    if length <= width {
+        let mut fitted = label.clone();
+        fitted.append_plain(" ".repeat(width - length));
+        return fitted;
+    }
+
+    let mut fitted = StyledString::new();
+    let mut left = width.saturating_sub(1);
+    for span in label.spans() {
+        let (content, used) = table::truncate(span.content, left);
+        left -= used;
+        fitted.append_styled(content, *span.attr);
+    }
+    if width > 0 {
+        // A wide character that did not fit leaves a cell to fill
+        fitted.append_plain("…");
+        fitted.append_plain(" ".repeat(left));
     }
+    fitted
 }
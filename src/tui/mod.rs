@@ -1,6 +1,19 @@
 pub mod app;
+mod budget_view;
+mod contact_sheet_view;
+mod duplicates_view;
 mod filter_view;
 mod info_view;
+mod jobs;
 mod list_view;
+mod playback_view;
+mod poster_view;
+mod query_view;
+mod queue_view;
+mod search_view;
 mod stats_view;
+mod subtitles_view;
+mod surprise_view;
+mod tags_view;
+mod tracks_view;
 mod user_data;
@@ -0,0 +1,120 @@
+use cursive::{
+    Cursive,
+    event::{Event, Key},
+    view::Nameable,
+    views::{Checkbox, Dialog, LinearLayout, OnEventView, SelectView, TextView},
+};
+
+use crate::{
+    movie::{self, MAX_RATING},
+    tui::{
+        jobs::Jobs,
+        list_view::{Item, ListView},
+        user_data::UserData,
+    },
+};
+
+const WATCHED_ID: &str = "playback_watched";
+const RATING_ID: &str = "playback_rating";
+
+pub struct PlaybackView;
+
+impl PlaybackView {
+    /// Asks what to record once the player was closed: whether the movie or
+    /// episode was watched, unless that was already decided, and a rating for
+    /// movies. When asked, the answer also decides whether the play queue
+    /// goes on.
+    pub fn prompt(siv: &mut Cursive, item: Item, ask_watched: bool) {
+        let rating = match &item {
+            Item::Movie(name) => siv
+                .user_data::<UserData>()
+                .map(|user_data| user_data.archive().get_movie(name).rating()),
+            _ if ask_watched => None,
+            _ => return,
+        };
+
+        let mut content = LinearLayout::vertical();
+        if ask_watched {
+            content.add_child(
+                LinearLayout::horizontal()
+                    .child(Checkbox::new().checked().with_name(WATCHED_ID))
+                    .child(TextView::new(" Mark as watched")),
+            );
+        }
+        if let Some(rating) = rating {
+            let ratings = (1..=MAX_RATING).map(|stars| (movie::pretty_rating(stars), stars));
+            let select = SelectView::new()
+                .popup()
+                .item("Not rated", 0)
+                .with_all(ratings)
+                .selected(rating.map_or(0, usize::from));
+            content.add_child(
+                LinearLayout::horizontal()
+                    .child(TextView::new("Rating: "))
+                    .child(select.with_name(RATING_ID)),
+            );
+        }
+
+        let title = match &item {
+            Item::Movie(name) | Item::Series(name) => name.clone(),
+            Item::Episode(_, path) => path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        };
+        let dialog = Dialog::around(content)
+            .title(title)
+            .button("Save", move |siv| {
+                let watched = siv
+                    .call_on_name(WATCHED_ID, |c: &mut Checkbox| c.is_checked())
+                    .unwrap_or(false);
+                let rating = siv
+                    .call_on_name(RATING_ID, |s: &mut SelectView<u8>| {
+                        s.selection().map(|rating| *rating)
+                    })
+                    .flatten();
+                siv.pop_layer();
+                Self::save(siv, &item, watched, rating);
+                if ask_watched {
+                    Jobs::continue_queue(siv, watched);
+                }
+            })
+            .button("Skip", move |siv| Self::skip(siv, ask_watched));
+
+        siv.add_layer(
+            OnEventView::new(dialog).on_pre_event(Event::Key(Key::Esc), move |app| {
+                Self::skip(app, ask_watched);
+            }),
+        );
+    }
+
+    /// Closes the prompt without recording anything, stopping the play queue
+    /// if it was waiting for the answer.
+    fn skip(siv: &mut Cursive, ask_watched: bool) {
+        siv.pop_layer();
+        if ask_watched {
+            Jobs::continue_queue(siv, false);
+        }
+    }
+
+    fn save(siv: &mut Cursive, item: &Item, watched: bool, rating: Option<u8>) {
+        siv.with_user_data(|user_data: &mut UserData| {
+            let archive = user_data.archive_mut();
+            match item {
+                Item::Movie(name) => {
+                    if watched {
+                        archive.set_watched(name);
+                    }
+                    if let Some(rating) = rating {
+                        archive.set_rating(name, Some(rating).filter(|&rating| rating > 0));
+                    }
+                }
+                Item::Episode(series, path) if watched => archive.set_episode_watched(series, path),
+                Item::Episode(..) | Item::Series(_) => {}
+            }
+            archive.save().ok();
+        });
+
+        ListView::refresh(siv);
+    }
+}
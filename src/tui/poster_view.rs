@@ -0,0 +1,275 @@
+use std::{
+    collections::VecDeque,
+    fs::OpenOptions,
+    io::Write,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use cursive::{
+    Cursive, Printer, Rect, Vec2, View,
+    backends::crossterm::crossterm::terminal,
+    style::{Color, ColorStyle},
+    view::{Nameable, ViewWrapper},
+    views::{NamedView, Panel},
+};
+
+use crate::{
+    artwork::{Artwork, Image},
+    config::Config,
+    graphics::Protocol,
+    tui::{
+        contact_sheet_view::CONTACT_SHEET_ID,
+        jobs::Jobs,
+        list_view::{Item, ListView},
+        user_data::UserData,
+    },
+};
+
+pub const POSTER_ID: &str = "poster";
+/// Identifier of the poster image for graphics protocols that need one.
+const POSTER_IMAGE_ID: u32 = 1;
+
+/// Cell size assumed when the terminal does not report its size in pixels.
+const DEFAULT_CELL_SIZE: (usize, usize) = (8, 16);
+/// Scaled images kept by a view, so that coming back to a movie does not
+/// decode its poster again.
+const CACHED_IMAGES: usize = 16;
+
+/// Image file and the size in pixels it is scaled to.
+pub type ImageKey = (PathBuf, usize, usize);
+
+/// Area showing an image file. Half-block art is drawn like any other view,
+/// while pixel-based images are written to the terminal after each refresh
+/// by `PosterView::draw_graphics`. Images are loaded in the background, the
+/// view staying empty meanwhile.
+pub struct Poster {
+    protocol: Protocol,
+    id: u32,
+    path: Option<PathBuf>,
+    /// Half-block rendering of the poster and the size it was made for
+    blocks: Option<(Vec2, Arc<Image>)>,
+    /// Scaled images, most recent last, `None` when the file is unreadable
+    images: VecDeque<(ImageKey, Option<Arc<Image>>)>,
+    /// Image needed but not loaded yet
+    missing: Option<ImageKey>,
+    /// Image being loaded
+    loading: Option<ImageKey>,
+    /// Where the view was last drawn on screen
+    area: Mutex<Option<Rect>>,
+    /// Poster and area last sent to the terminal
+    shown: Option<(PathBuf, Rect)>,
+}
+
+impl View for Poster {
+    fn draw(&self, printer: &Printer) {
+        if let Ok(mut area) = self.area.lock() {
+            *area = Some(Rect::from_size(printer.offset, printer.size));
+        }
+
+        let Some((size, image)) = &self.blocks else {
+            return;
+        };
+        for y in 0..size.y {
+            for x in 0..size.x {
+                let [top, bottom] = [image.pixel(x, y * 2), image.pixel(x, y * 2 + 1)];
+                let style = ColorStyle::new(
+                    Color::Rgb(top[0], top[1], top[2]),
+                    Color::Rgb(bottom[0], bottom[1], bottom[2]),
+                );
+                printer.with_color(style, |printer| printer.print((x, y), "▀"));
+            }
+        }
+    }
+
+    fn layout(&mut self, size: Vec2) {
+        if self.protocol != Protocol::Blocks {
+            return;
+        }
+
+        self.blocks = self
+            .path
+            .clone()
+            .and_then(|path| Some((size, self.image((path, size.x, size.y * 2))?)));
+    }
+
+    fn required_size(&mut self, constraint: Vec2) -> Vec2 {
+        constraint
+    }
+}
+
+impl Poster {
+    pub fn new(path: Option<PathBuf>, id: u32) -> Self {
+        Self {
+            protocol: Config::get().graphics.resolve(),
+            id,
+            path,
+            blocks: None,
+            images: VecDeque::new(),
+            missing: None,
+            loading: None,
+            area: Mutex::new(None),
+            shown: None,
+        }
+    }
+
+    /// Cached image, or `None` after noting it as missing when it was not
+    /// loaded yet.
+    fn image(&mut self, key: ImageKey) -> Option<Arc<Image>> {
+        if let Some((_, image)) = self.images.iter().find(|(cached, _)| *cached == key) {
+            return image.clone();
+        }
+        self.missing = Some(key);
+        None
+    }
+
+    /// Image to load for the view, unless it is already being loaded.
+    fn take_missing(&mut self) -> Option<ImageKey> {
+        let key = self.missing.take()?;
+        if self.loading.as_ref() == Some(&key) {
+            return None;
+        }
+        self.loading = Some(key.clone());
+        Some(key)
+    }
+
+    /// Keeps a loaded image, dropping the oldest one if the cache is full.
+    pub fn add_image(&mut self, key: ImageKey, image: Option<Image>) {
+        if self.loading.as_ref() == Some(&key) {
+            self.loading = None;
+        }
+        if self.images.len() >= CACHED_IMAGES {
+            self.images.pop_front();
+        }
+        self.images.push_back((key, image.map(Arc::new)));
+    }
+
+    /// Removes the image from the terminal, to be called before the view
+    /// goes away.
+    pub fn hide(&mut self) {
+        self.path = None;
+        self.show(false);
+    }
+
+    /// Sends the poster to the terminal, or removes it, when it or its
+    /// place on screen changed since the last call.
+    fn show(&mut self, covered: bool) {
+        if !self.protocol.is_pixel_based() {
+            return;
+        }
+
+        let area = self.area.lock().ok().and_then(|area| *area);
+        let wanted = self.path.clone().zip(area).filter(|_| !covered);
+        let (cell_width, cell_height) = Self::cell_size();
+        let image = wanted.clone().and_then(|(path, area)| {
+            self.image((path, area.width() * cell_width, area.height() * cell_height))
+        });
+        // Nothing is shown until the image is loaded
+        let wanted = wanted.filter(|_| image.is_some());
+        if wanted == self.shown {
+            return;
+        }
+
+        let Ok(mut tty) = OpenOptions::new().write(true).open("/dev/tty") else {
+            return;
+        };
+        let mut sequence = String::from("\x1b7");
+
+        if let Some((_, old_area)) = self.shown.take() {
+            match self.protocol.clear(self.id) {
+                Some(clear) => sequence.push_str(&clear),
+                // Text overwrites the image, but cursive only redraws the
+                // cells it believes changed
+                None if wanted.is_none() && !covered && area == Some(old_area) => {
+                    for row in 0..old_area.height() {
+                        let top_left = old_area.top_left();
+                        sequence.push_str(&Self::move_to(top_left.x, top_left.y + row));
+                        sequence.push_str(&" ".repeat(old_area.width()));
+                    }
+                }
+                None => {}
+            }
+        }
+
+        if let Some(((_, area), image)) = wanted.as_ref().zip(image) {
+            let top_left = area.top_left();
+            sequence.push_str(&Self::move_to(top_left.x, top_left.y));
+            sequence.push_str(
+                &self
+                    .protocol
+                    .encode(&image, area.width(), area.height(), self.id),
+            );
+        }
+
+        sequence.push_str("\x1b8");
+        tty.write_all(sequence.as_bytes()).ok();
+        tty.flush().ok();
+        self.shown = wanted;
+    }
+
+    fn move_to(x: usize, y: usize) -> String {
+        format!("\x1b[{};{}H", y + 1, x + 1)
+    }
+
+    fn cell_size() -> (usize, usize) {
+        terminal::window_size()
+            .ok()
+            .filter(|size| size.width > 0 && size.columns > 0 && size.rows > 0)
+            .map_or(DEFAULT_CELL_SIZE, |size| {
+                (
+                    usize::from(size.width / size.columns),
+                    usize::from(size.height / size.rows),
+                )
+            })
+    }
+}
+
+type ViewType = Panel<NamedView<Poster>>;
+
+pub struct PosterView {
+    view: ViewType,
+}
+
+impl ViewWrapper for PosterView {
+    cursive::wrap_impl!(self.view: ViewType);
+}
+
+impl PosterView {
+    pub fn new() -> Self {
+        Self {
+            view: Panel::new(Poster::new(None, POSTER_IMAGE_ID).with_name(POSTER_ID)),
+        }
+    }
+
+    pub fn refresh(siv: &mut Cursive) {
+        let item = ListView::get_selected(siv);
+
+        let path = siv.user_data().and_then(|d: &mut UserData| match item? {
+            Item::Movie(name) => d
+                .archive()
+                .movies
+                .iter()
+                .find(|m| m.name() == name)
+                .and_then(Artwork::find),
+            Item::Series(_) | Item::Episode(..) => None,
+        });
+
+        siv.call_on_name(POSTER_ID, |v: &mut Poster| v.path = path);
+    }
+
+    /// Writes the images on screen to the terminal when drawn with pixels,
+    /// and starts loading the ones that are missing. The poster is hidden
+    /// while a dialog is open, and everything once the app stops running.
+    pub fn draw_graphics(siv: &mut Cursive) {
+        let running = siv.is_running();
+        let covered = siv.screen().len() > 1 || !running;
+        siv.call_on_name(POSTER_ID, |v: &mut Poster| v.show(covered));
+        siv.call_on_name(CONTACT_SHEET_ID, |v: &mut Poster| v.show(!running));
+
+        for name in [POSTER_ID, CONTACT_SHEET_ID] {
+            if let Some(key) = siv.call_on_name(name, Poster::take_missing).flatten() {
+                Jobs::image(siv, name, key);
+            }
+        }
+    }
+}
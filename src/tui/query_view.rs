@@ -0,0 +1,187 @@
+use cursive::{
+    Cursive,
+    event::{Event, Key},
+    view::{Nameable, Resizable},
+    views::{Dialog, EditView, LinearLayout, OnEventView, SelectView, TextView},
+};
+
+use crate::{
+    query::{self, Query},
+    tui::{filter_view::FilterView, list_view::ListView, user_data::UserData},
+};
+
+const QUERY_ID: &str = "query";
+const LIST_NAME_ID: &str = "query_list_name";
+const SMART_LISTS_ID: &str = "smart_lists";
+
+pub struct QueryView;
+
+impl QueryView {
+    /// Asks for a query narrowing the list down, on top of the filter.
+    pub fn show(siv: &mut Cursive) {
+        let text = siv
+            .user_data::<UserData>()
+            .and_then(|user_data| user_data.query().map(Query::to_string))
+            .unwrap_or_default();
+
+        let content = LinearLayout::vertical()
+            .child(
+                EditView::new()
+                    .content(text)
+                    .on_submit(|siv, text| {
+                        if Self::apply(siv, text) {
+                            siv.pop_layer();
+                        }
+                    })
+                    .with_name(QUERY_ID)
+                    .min_width(50),
+            )
+            .child(TextView::new(query::SYNTAX));
+        let dialog = Dialog::around(content)
+            .title("Query")
+            .button("Apply", |siv| {
+                let text = Self::text(siv);
+                if Self::apply(siv, &text) {
+                    siv.pop_layer();
+                }
+            })
+            .button("Save as...", |siv| {
+                let text = Self::text(siv);
+                if !text.trim().is_empty() && Self::apply(siv, &text) {
+                    siv.pop_layer();
+                    Self::ask_name(siv, &text);
+                }
+            })
+            .button("Clear", |siv| {
+                siv.pop_layer();
+                Self::apply(siv, "");
+            })
+            .dismiss_button("Cancel");
+
+        siv.add_layer(
+            OnEventView::new(dialog).on_pre_event(Event::Key(Key::Esc), |app| {
+                app.pop_layer();
+            }),
+        );
+    }
+
+    /// Lists the saved queries to apply one, 'd' deleting the selected one.
+    pub fn show_smart_lists(siv: &mut Cursive) {
+        let Some(lists) = siv.user_data::<UserData>().map(|user_data| {
+            user_data
+                .archive()
+                .smart_lists()
+                .iter()
+                .map(|(name, text)| (format!("{name}: {text}"), (name.clone(), text.clone())))
+                .collect::<Vec<_>>()
+        }) else {
+            return;
+        };
+        if lists.is_empty() {
+            siv.add_layer(Dialog::info(
+                "No smart lists yet: save a query with 'f' first",
+            ));
+            return;
+        }
+
+        let select = SelectView::new()
+            .with_all(lists)
+            .on_submit(|siv, (_, text): &(String, String)| {
+                if Self::apply(siv, text) {
+                    siv.pop_layer();
+                }
+            })
+            .with_name(SMART_LISTS_ID);
+        let dialog = Dialog::around(select)
+            .title("Smart lists")
+            .dismiss_button("Close");
+
+        siv.add_layer(
+            OnEventView::new(dialog)
+                .on_pre_event(Event::Key(Key::Esc), |app| {
+                    app.pop_layer();
+                })
+                .on_event('d', Self::delete_selected),
+        );
+    }
+
+    fn text(siv: &mut Cursive) -> String {
+        siv.call_on_name(QUERY_ID, |e: &mut EditView| e.get_content())
+            .map(|text| text.to_string())
+            .unwrap_or_default()
+    }
+
+    /// Filters the list with a query, or stops filtering if it is empty.
+    /// Returns false if the query is invalid.
+    fn apply(siv: &mut Cursive, text: &str) -> bool {
+        let query = if text.trim().is_empty() {
+            None
+        } else {
+            match Query::parse(text) {
+                Ok(query) => Some(query),
+                Err(e) => {
+                    siv.add_layer(Dialog::info(format!("Invalid query: {e}")));
+                    return false;
+                }
+            }
+        };
+
+        siv.with_user_data(|user_data: &mut UserData| user_data.set_query(query));
+        FilterView::refresh(siv);
+        ListView::refresh(siv);
+        true
+    }
+
+    fn ask_name(siv: &mut Cursive, text: &str) {
+        let save = move |siv: &mut Cursive| {
+            let name = siv
+                .call_on_name(LIST_NAME_ID, |e: &mut EditView| e.get_content())
+                .map(|name| name.trim().to_owned())
+                .unwrap_or_default();
+            if name.is_empty() {
+                return;
+            }
+            siv.pop_layer();
+            siv.with_user_data(|user_data: &mut UserData| {
+                if let Some(query) = user_data.query().cloned() {
+                    let archive = user_data.archive_mut();
+                    archive.save_smart_list(&name, &query);
+                    archive.save().ok();
+                }
+            });
+        };
+
+        let dialog = Dialog::around(EditView::new().with_name(LIST_NAME_ID).min_width(30))
+            .title(format!("Save \"{text}\" as"))
+            .button("Save", save)
+            .dismiss_button("Cancel");
+
+        siv.add_layer(
+            OnEventView::new(dialog).on_pre_event(Event::Key(Key::Esc), |app| {
+                app.pop_layer();
+            }),
+        );
+    }
+
+    fn delete_selected(siv: &mut Cursive) {
+        let Some((index, name)) = siv
+            .call_on_name(SMART_LISTS_ID, |s: &mut SelectView<(String, String)>| {
+                let index = s.selected_id()?;
+                let (_, (name, _)) = s.get_item(index)?;
+                Some((index, name.clone()))
+            })
+            .flatten()
+        else {
+            return;
+        };
+
+        siv.with_user_data(|user_data: &mut UserData| {
+            let archive = user_data.archive_mut();
+            archive.delete_smart_list(&name);
+            archive.save().ok();
+        });
+        siv.call_on_name(SMART_LISTS_ID, |s: &mut SelectView<(String, String)>| {
+            s.remove_item(index);
+        });
+    }
+}
@@ -0,0 +1,136 @@
+use std::path::{Path, PathBuf};
+
+use cursive::{
+    Cursive,
+    event::{Event, Key},
+    view::{Nameable, Resizable, Scrollable},
+    views::{Dialog, OnEventView, SelectView},
+};
+
+use crate::{
+    archive::Archive,
+    tui::{
+        jobs::Jobs,
+        list_view::{Item, ListView},
+        user_data::UserData,
+    },
+};
+
+const QUEUE_ID: &str = "play_queue";
+
+pub struct QueueView;
+
+impl QueueView {
+    /// Opens the play queue, where entries can be moved with 'K'/'J',
+    /// removed with 'd' and played one after another.
+    pub fn show(siv: &mut Cursive) {
+        let list = SelectView::<PathBuf>::new()
+            .with_name(QUEUE_ID)
+            .scrollable()
+            .min_width(40)
+            .max_height(20);
+
+        let dialog = Dialog::around(list)
+            .title("Play queue")
+            .button("Play", |siv| {
+                siv.pop_layer();
+                if Jobs::is_playing(siv) {
+                    siv.add_layer(Dialog::info("The player is already open"));
+                    return;
+                }
+                ListView::play_queued(siv);
+            })
+            .dismiss_button("Close");
+
+        siv.add_layer(
+            OnEventView::new(dialog)
+                .on_pre_event(Event::Key(Key::Esc), |app| {
+                    app.pop_layer();
+                })
+                .on_event('K', |siv| Self::move_selected(siv, true))
+                .on_event('J', |siv| Self::move_selected(siv, false))
+                .on_event('d', Self::remove_selected),
+        );
+        Self::refresh(siv, 0);
+    }
+
+    fn refresh(siv: &mut Cursive, selected: usize) {
+        let Some(items) = siv.user_data::<UserData>().map(|user_data| {
+            let archive = user_data.archive();
+            archive
+                .play_queue()
+                .iter()
+                .map(|path| (Self::label(archive, path), path.clone()))
+                .collect::<Vec<_>>()
+        }) else {
+            return;
+        };
+
+        siv.call_on_name(QUEUE_ID, |view: &mut SelectView<PathBuf>| {
+            view.clear();
+            view.add_all(items);
+            view.set_selection(selected);
+        });
+    }
+
+    /// Name of the movie or episode, or the file name if it is no longer in
+    /// the library.
+    fn label(archive: &Archive, path: &Path) -> String {
+        match Item::from_queued(archive, path) {
+            Some(Item::Movie(name)) => name,
+            Some(Item::Episode(series, path)) => archive
+                .get_series(&series)
+                .and_then(|s| s.episodes().find(|e| e.path() == path))
+                .map_or_else(
+                    || series.clone(),
+                    |episode| format!("{series} {}", episode.label()),
+                ),
+            _ => format!(
+                "{} (missing)",
+                path.file_name().unwrap_or_default().to_string_lossy()
+            ),
+        }
+    }
+
+    fn move_selected(siv: &mut Cursive, earlier: bool) {
+        let Some(index) = Self::selected(siv) else {
+            return;
+        };
+        let Some(moved) = siv.user_data::<UserData>().map(|user_data| {
+            let archive = user_data.archive_mut();
+            let moved = archive.move_queued(index, earlier);
+            archive.save().ok();
+            moved
+        }) else {
+            return;
+        };
+
+        Self::refresh(siv, moved);
+    }
+
+    fn remove_selected(siv: &mut Cursive) {
+        let Some(path) = siv
+            .call_on_name(QUEUE_ID, |view: &mut SelectView<PathBuf>| {
+                view.selection().map(|path| (*path).clone())
+            })
+            .flatten()
+        else {
+            return;
+        };
+        let index = Self::selected(siv).unwrap_or_default();
+        siv.with_user_data(|user_data: &mut UserData| {
+            let archive = user_data.archive_mut();
+            archive.dequeue(&path);
+            archive.save().ok();
+        });
+
+        Self::refresh(siv, index);
+    }
+
+    fn selected(siv: &mut Cursive) -> Option<usize> {
+        siv.call_on_name(QUEUE_ID, |view: &mut SelectView<PathBuf>| {
+            view.selected_id()
+        })
+        .flatten()
+    }
+}
@@ -0,0 +1,99 @@
+use cursive::{
+    Cursive,
+    event::{Event, Key},
+    view::{Nameable, Resizable},
+    views::{Dialog, EditView, OnEventView, SelectView},
+};
+
+use crate::tui::{
+    list_view::{Item, ListView, SELECT_ID},
+    user_data::UserData,
+};
+
+const SEARCH_ID: &str = "search";
+
+pub struct SearchView;
+
+impl SearchView {
+    /// Opens the search prompt. The list only shows what matches while the
+    /// search is typed; once submitted the whole list is back, with the
+    /// matches highlighted to jump between them.
+    pub fn show(siv: &mut Cursive) {
+        let search = siv
+            .user_data::<UserData>()
+            .map(|user_data| user_data.search().to_owned())
+            .unwrap_or_default();
+
+        let edit = EditView::new()
+            .content(search.clone())
+            .on_edit(|siv, search, _| Self::update(siv, search))
+            .on_submit(|siv, _| Self::submit(siv))
+            .with_name(SEARCH_ID)
+            .min_width(30);
+        let dialog = Dialog::around(edit).title("Search");
+
+        siv.add_layer(
+            OnEventView::new(dialog)
+                .on_pre_event(Event::Key(Key::Esc), Self::cancel)
+                .on_pre_event(Event::Key(Key::Down), |siv| Self::move_selection(siv, true))
+                .on_pre_event(Event::Key(Key::Up), |siv| Self::move_selection(siv, false)),
+        );
+        Self::update(siv, &search);
+    }
+
+    /// Narrows the list down to the entries matching the search, selecting
+    /// the first one.
+    fn update(siv: &mut Cursive, search: &str) {
+        siv.with_user_data(|user_data: &mut UserData| {
+            user_data.set_search(search.to_owned(), true);
+        });
+        ListView::refresh(siv);
+
+        siv.call_on_name(SELECT_ID, |s: &mut SelectView<Item>| {
+            // The callback only refreshes the info, done below
+            s.set_selection(0);
+        });
+        Self::show_selected(siv);
+    }
+
+    /// Keeps the search to jump between matches, showing the whole list
+    /// again.
+    fn submit(siv: &mut Cursive) {
+        siv.pop_layer();
+        siv.with_user_data(|user_data: &mut UserData| {
+            let search = user_data.search().to_owned();
+            user_data.set_search(search, false);
+        });
+        ListView::refresh(siv);
+        Self::show_selected(siv);
+    }
+
+    /// Forgets the search.
+    fn cancel(siv: &mut Cursive) {
+        siv.pop_layer();
+        siv.with_user_data(|user_data: &mut UserData| {
+            user_data.set_search(String::new(), false);
+        });
+        ListView::refresh(siv);
+        Self::show_selected(siv);
+    }
+
+    /// Moves through the matches without leaving the prompt.
+    fn move_selection(siv: &mut Cursive, down: bool) {
+        siv.call_on_name(SELECT_ID, |s: &mut SelectView<Item>| {
+            if down {
+                s.select_down(1);
+            } else {
+                s.select_up(1);
+            }
+        });
+        Self::show_selected(siv);
+    }
+
+    /// Refreshes the info of the selected entry and scrolls to it.
+    fn show_selected(siv: &mut Cursive) {
+        if let Some(item) = ListView::get_selected(siv) {
+            ListView::select(siv, &item);
+        }
+    }
+}
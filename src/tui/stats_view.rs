@@ -36,6 +36,8 @@ impl StatsView {
         let time = SystemTime::now();
         let movies = &user_data.archive().movies;
         let total_num = movies.len();
+        let series_num = user_data.archive().series.len();
+        let broken_num = movies.iter().filter(|m| m.is_broken()).count();
 
         let (watched_num, recent_num) = movies.iter().fold((0, 0), |(watched, recent), m| {
             let is_watched = m.since_watched().is_some();
@@ -55,7 +57,9 @@ impl StatsView {
             + "WATCHED:\n"
             + &format!("├ last 14d: {recent_num}\n")
             + &format!("└ total: {watched_num}\n")
-            + &format!("REMAINING: {remaining}");
+            + &format!("REMAINING: {remaining}\n")
+            + &format!("SERIES: {series_num}\n")
+            + &format!("BROKEN: {broken_num}");
 
         siv.call_on_name(STATS_ID, |v: &mut TextView| {
             v.set_content(content);
@@ -0,0 +1,91 @@
+use std::path::PathBuf;
+
+use cursive::{
+    Cursive,
+    event::{Event, Key},
+    view::Nameable,
+    views::{Checkbox, Dialog, LinearLayout, OnEventView, TextView},
+};
+
+use crate::{
+    tracks::Track,
+    tui::{
+        jobs::Jobs,
+        list_view::{Item, ListView},
+        user_data::UserData,
+    },
+};
+
+pub struct SubtitlesView;
+
+impl SubtitlesView {
+    /// Lets the user pick the text subtitle streams of the selected movie to
+    /// extract to sidecar files, once they are read in the background.
+    pub fn show(siv: &mut Cursive) {
+        let Some(Item::Movie(name)) = ListView::get_selected(siv) else {
+            return;
+        };
+        let Some(paths) = siv.user_data::<UserData>().and_then(|user_data| {
+            let movie = user_data.archive().get_movie(&name);
+            (!movie.is_disc()).then(|| movie.paths().cloned().collect::<Vec<PathBuf>>())
+        }) else {
+            return;
+        };
+
+        Jobs::subtitle_tracks(siv, paths);
+    }
+
+    /// Shows the subtitle streams read from the first file of a movie.
+    pub fn open(siv: &mut Cursive, paths: Vec<PathBuf>, tracks: Result<Vec<Track>, String>) {
+        let tracks = match tracks {
+            Ok(tracks) if !tracks.is_empty() => tracks,
+            Ok(_) => {
+                siv.add_layer(Dialog::info("No text subtitle streams"));
+                return;
+            }
+            Err(e) => {
+                siv.add_layer(Dialog::info(format!("Could not read subtitles: {e}")));
+                return;
+            }
+        };
+
+        let mut list = LinearLayout::vertical();
+        for track in &tracks {
+            list.add_child(
+                LinearLayout::horizontal()
+                    .child(Checkbox::new().with_name(Self::checkbox_id(track.index())))
+                    .child(TextView::new(format!(" {}", track.label()))),
+            );
+        }
+
+        let dialog = Dialog::around(list)
+            .title("Extract subtitles")
+            .button("Extract", move |siv| {
+                let indices: Vec<usize> = tracks
+                    .iter()
+                    .map(Track::index)
+                    .filter(|&index| {
+                        siv.call_on_name(&Self::checkbox_id(index), |c: &mut Checkbox| {
+                            c.is_checked()
+                        })
+                        .unwrap_or(false)
+                    })
+                    .collect();
+                siv.pop_layer();
+                if !indices.is_empty() {
+                    Jobs::subtitles(siv, paths.clone(), indices);
+                }
+            })
+            .dismiss_button("Cancel");
+
+        siv.add_layer(
+            OnEventView::new(dialog).on_pre_event(Event::Key(Key::Esc), |app| {
+                app.pop_layer();
+            }),
+        );
+    }
+
+    fn checkbox_id(index: usize) -> String {
+        format!("subtitle_{index}")
+    }
+}
@@ -0,0 +1,161 @@
+use cursive::{
+    Cursive,
+    event::{Event, Key},
+    view::{Nameable, Resizable},
+    views::{Checkbox, Dialog, EditView, LinearLayout, OnEventView, TextView},
+};
+
+use crate::{
+    budget::TimeBudget,
+    config::Config,
+    surprise::{Surprise, SurpriseSettings},
+    tui::{
+        list_view::{Item, ListView},
+        user_data::UserData,
+    },
+};
+
+const MAX_LENGTH_ID: &str = "surprise_max_length";
+const TAGS_ID: &str = "surprise_tags";
+const YEARS_ID: &str = "surprise_years";
+const OLDER_ID: &str = "surprise_older";
+
+pub struct SurpriseView;
+
+impl SurpriseView {
+    /// Asks for the constraints of a random pick among the movies shown in
+    /// the list, starting from the configured ones.
+    pub fn show(siv: &mut Cursive) {
+        let settings = &Config::get().surprise;
+        let number = |value: Option<u64>| value.map(|value| value.to_string()).unwrap_or_default();
+        let field = |label: &str, content: String, id: &str| {
+            LinearLayout::horizontal()
+                .child(TextView::new(label).fixed_width(32))
+                .child(
+                    EditView::new()
+                        .content(content)
+                        .with_name(id)
+                        .fixed_width(20),
+                )
+        };
+
+        let content = LinearLayout::vertical()
+            .child(field(
+                "Max length (minutes)",
+                number(settings.max_length),
+                MAX_LENGTH_ID,
+            ))
+            .child(field("Tags", settings.tags.join(", "), TAGS_ID))
+            .child(field(
+                "Not watched in the last (years)",
+                number(settings.not_watched_years),
+                YEARS_ID,
+            ))
+            .child(
+                LinearLayout::horizontal()
+                    .child(
+                        Checkbox::new()
+                            .with_checked(settings.favor_older)
+                            .with_name(OLDER_ID),
+                    )
+                    .child(TextView::new(" Favor older additions")),
+            );
+
+        let dialog = Dialog::around(content)
+            .title("Surprise me")
+            .button("Pick", |siv| {
+                let settings = Self::read_settings(siv);
+                siv.pop_layer();
+                Self::pick(siv, settings);
+            })
+            .dismiss_button("Cancel");
+
+        siv.add_layer(
+            OnEventView::new(dialog).on_pre_event(Event::Key(Key::Esc), |app| {
+                app.pop_layer();
+            }),
+        );
+    }
+
+    fn read_settings(siv: &mut Cursive) -> SurpriseSettings {
+        let mut text = |id: &str| {
+            siv.call_on_name(id, |e: &mut EditView| e.get_content())
+                .unwrap_or_default()
+        };
+        let max_length = text(MAX_LENGTH_ID).trim().parse().ok();
+        let tags = text(TAGS_ID)
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(str::to_owned)
+            .collect();
+        let not_watched_years = text(YEARS_ID).trim().parse().ok();
+        let favor_older = siv
+            .call_on_name(OLDER_ID, |c: &mut Checkbox| c.is_checked())
+            .unwrap_or(false);
+
+        SurpriseSettings {
+            max_length,
+            tags,
+            not_watched_years,
+            favor_older,
+            ..Config::get().surprise.clone()
+        }
+    }
+
+    /// Shows a random movie, remembered so that it is not picked again soon.
+    fn pick(siv: &mut Cursive, settings: SurpriseSettings) {
+        let Some(picked) = siv.user_data::<UserData>().and_then(|user_data| {
+            let now = TimeBudget::now();
+            let archive = user_data.archive();
+            let movies = archive
+                .movies
+                .iter()
+                .filter(|movie| ListView::shows_movie(user_data, movie, now));
+            let movie = Surprise::pick(movies, &settings, archive.recent_picks())?;
+            let picked = (
+                movie.name().to_owned(),
+                movie.pretty_length(),
+                movie.pretty_tags(),
+            );
+
+            let archive = user_data.archive_mut();
+            archive.record_pick(&picked.0, settings.recent_picks);
+            archive.save().ok();
+            Some(picked)
+        }) else {
+            siv.add_layer(Dialog::info("No movie meets the constraints"));
+            return;
+        };
+
+        let (name, length, tags) = picked;
+        let tags = if tags.is_empty() {
+            tags
+        } else {
+            format!("\nTags: {tags}")
+        };
+        let (play_name, show_name) = (name.clone(), name.clone());
+        let dialog = Dialog::text(format!("{name}\n\nLength: {length}{tags}"))
+            .title("Tonight's movie")
+            .button("Play", move |siv| {
+                siv.pop_layer();
+                ListView::select(siv, &Item::Movie(play_name.clone()));
+                ListView::play_item(siv, Item::Movie(play_name.clone()));
+            })
+            .button("Re-roll", move |siv| {
+                siv.pop_layer();
+                Self::pick(siv, settings.clone());
+            })
+            .button("Show", move |siv| {
+                siv.pop_layer();
+                ListView::select(siv, &Item::Movie(show_name.clone()));
+            })
+            .dismiss_button("Cancel");
+
+        siv.add_layer(
+            OnEventView::new(dialog).on_pre_event(Event::Key(Key::Esc), |app| {
+                app.pop_layer();
+            }),
+        );
+    }
+}
@@ -0,0 +1,65 @@
+use cursive::{
+    Cursive,
+    event::{Event, Key},
+    view::{Nameable, Resizable},
+    views::{Dialog, EditView, OnEventView},
+};
+
+use crate::tui::{
+    info_view::InfoView,
+    list_view::{Item, ListView},
+    user_data::UserData,
+};
+
+const TAGS_ID: &str = "tags_edit";
+
+pub struct TagsView;
+
+impl TagsView {
+    /// Lets the user edit the tags of the selected movie, separated by
+    /// commas.
+    pub fn show(siv: &mut Cursive) {
+        let Some(Item::Movie(name)) = ListView::get_selected(siv) else {
+            return;
+        };
+        let Some(tags) = siv
+            .user_data::<UserData>()
+            .map(|user_data| user_data.archive().get_movie(&name).pretty_tags())
+        else {
+            return;
+        };
+
+        let submit_name = name.clone();
+        let edit = EditView::new()
+            .content(tags)
+            .on_submit(move |siv, tags| Self::save(siv, &submit_name, tags))
+            .with_name(TAGS_ID)
+            .min_width(30);
+        let dialog = Dialog::around(edit)
+            .title(format!("Tags of {name}"))
+            .button("Save", move |siv| {
+                let tags = siv
+                    .call_on_name(TAGS_ID, |e: &mut EditView| e.get_content())
+                    .unwrap_or_default();
+                Self::save(siv, &name, &tags);
+            })
+            .dismiss_button("Cancel");
+
+        siv.add_layer(
+            OnEventView::new(dialog).on_pre_event(Event::Key(Key::Esc), |app| {
+                app.pop_layer();
+            }),
+        );
+    }
+
+    fn save(siv: &mut Cursive, name: &str, tags: &str) {
+        siv.pop_layer();
+        siv.with_user_data(|user_data: &mut UserData| {
+            let archive = user_data.archive_mut();
+            archive.set_tags(name, tags.split(',').map(str::to_owned).collect());
+            archive.save().ok();
+        });
+
+        InfoView::refresh(siv);
+    }
+}
@@ -0,0 +1,121 @@
+use cursive::{
+    Cursive,
+    event::{Event, Key},
+    view::Nameable,
+    views::{Dialog, LinearLayout, OnEventView, SelectView, TextView},
+};
+
+use crate::{
+    tracks::{Track, TrackChoice, TrackChoices, Tracks},
+    tui::{
+        jobs::Jobs,
+        list_view::{Item, ListView},
+        user_data::UserData,
+    },
+};
+
+const AUDIO_ID: &str = "tracks_audio";
+const SUBTITLE_ID: &str = "tracks_subtitle";
+
+pub struct TracksView;
+
+impl TracksView {
+    /// Lets the user pick the audio and subtitle tracks of the selected
+    /// movie, remembered for the next times it is played, once they are
+    /// read in the background.
+    pub fn show(siv: &mut Cursive) {
+        let Some(Item::Movie(name)) = ListView::get_selected(siv) else {
+            return;
+        };
+        let Some(file) = siv.user_data::<UserData>().and_then(|user_data| {
+            let movie = user_data.archive().get_movie(&name);
+            (!movie.is_disc()).then(|| movie.paths().next().cloned())?
+        }) else {
+            return;
+        };
+
+        Jobs::tracks(siv, name, file);
+    }
+
+    /// Shows the tracks read from the first file of a movie, with the ones
+    /// picked before selected.
+    pub fn open(siv: &mut Cursive, name: String, tracks: Result<Tracks, String>) {
+        let tracks = match tracks {
+            Ok(tracks) => tracks,
+            Err(e) => {
+                siv.add_layer(Dialog::info(format!("Could not read tracks: {e}")));
+                return;
+            }
+        };
+        let Some(choices) = siv
+            .user_data::<UserData>()
+            .map(|user_data| user_data.archive().get_movie(&name).tracks())
+        else {
+            return;
+        };
+
+        let audio = Self::select(&tracks.audio, choices.audio, false);
+        let subtitle = Self::select(&tracks.subtitles, choices.subtitle, true);
+        let content = LinearLayout::vertical()
+            .child(TextView::new("Audio"))
+            .child(audio.with_name(AUDIO_ID))
+            .child(TextView::new("Subtitles"))
+            .child(subtitle.with_name(SUBTITLE_ID));
+
+        let play_name = name.clone();
+        let dialog = Dialog::around(content)
+            .title("Tracks")
+            .button("Play", move |siv| {
+                Self::save(siv, &play_name);
+                ListView::play_item(siv, Item::Movie(play_name.clone()));
+            })
+            .button("Save", move |siv| Self::save(siv, &name))
+            .dismiss_button("Cancel");
+
+        siv.add_layer(
+            OnEventView::new(dialog).on_pre_event(Event::Key(Key::Esc), |app| {
+                app.pop_layer();
+            }),
+        );
+    }
+
+    fn select(tracks: &[Track], choice: TrackChoice, can_be_off: bool) -> SelectView<TrackChoice> {
+        let mut select = SelectView::new()
+            .popup()
+            .item("Automatic", TrackChoice::Auto);
+        if can_be_off {
+            select.add_item("Off", TrackChoice::Off);
+        }
+        select.add_all(
+            tracks
+                .iter()
+                .map(|track| (track.label(), TrackChoice::Track(track.number()))),
+        );
+
+        let selected = (0..select.len())
+            .find(|&i| select.get_item(i).is_some_and(|(_, item)| *item == choice))
+            .unwrap_or_default();
+        select.selected(selected)
+    }
+
+    fn save(siv: &mut Cursive, name: &str) {
+        let choice = |siv: &mut Cursive, id: &str| {
+            siv.call_on_name(id, |s: &mut SelectView<TrackChoice>| {
+                s.selection().map(|choice| *choice)
+            })
+            .flatten()
+            .unwrap_or_default()
+        };
+        let tracks = TrackChoices {
+            audio: choice(siv, AUDIO_ID),
+            subtitle: choice(siv, SUBTITLE_ID),
+        };
+        siv.pop_layer();
+
+        siv.with_user_data(|user_data: &mut UserData| {
+            let archive = user_data.archive_mut();
+            archive.set_tracks(name, tracks);
+            archive.save().ok();
+        });
+    }
+}
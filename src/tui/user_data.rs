@@ -1,15 +1,54 @@
-use crate::{archive::Archive, tui::filter_view::Filter};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    archive::Archive, budget::TimeBudget, config::Config, query::Query, table::Column,
+    tui::filter_view::Filter,
+};
 
 pub struct UserData {
     archive: Archive,
     filter: Filter,
+    /// Query narrowing the list down further, if any
+    query: Option<Query>,
+    /// Names of the series whose episodes are shown in the list
+    expanded: BTreeSet<String>,
+    /// Progress of the background jobs currently running, by job name
+    jobs: BTreeMap<&'static str, String>,
+    /// Entry of the play queue being played while the queue is run through
+    queued: Option<PathBuf>,
+    /// Deadline movies have to fit before, if any
+    budget: Option<TimeBudget>,
+    /// Text searched for, highlighted in the list
+    search: String,
+    /// Whether the list only shows the entries matching the search, while
+    /// it is typed
+    is_searching: bool,
+    /// Whether the list is shown as a table rather than a list of titles
+    is_table: bool,
+    /// Characters available for a row of the list
+    list_width: usize,
+    /// Columns of the table as last shown, with their width
+    columns: Vec<(Column, usize)>,
 }
 
 impl UserData {
-    pub const fn new(archive: Archive) -> Self {
+    pub fn new(archive: Archive) -> Self {
         Self {
             archive,
             filter: Filter::Empty,
+            query: None,
+            expanded: BTreeSet::new(),
+            jobs: BTreeMap::new(),
+            queued: None,
+            budget: None,
+            search: String::new(),
+            is_searching: false,
+            is_table: Config::get().table.enabled,
+            list_width: 0,
+            columns: Vec::new(),
         }
     }
 
@@ -21,6 +60,18 @@ impl UserData {
         self.filter
     }
 
+    pub const fn query(&self) -> Option<&Query> {
+        self.query.as_ref()
+    }
+
+    pub fn set_query(&mut self, query: Option<Query>) {
+        self.query = query;
+    }
+
+    pub const fn expanded(&self) -> &BTreeSet<String> {
+        &self.expanded
+    }
+
     pub const fn archive_mut(&mut self) -> &mut Archive {
         &mut self.archive
     }
@@ -28,4 +79,86 @@ impl UserData {
     pub const fn filter_mut(&mut self) -> &mut Filter {
         &mut self.filter
     }
+
+    pub const fn expanded_mut(&mut self) -> &mut BTreeSet<String> {
+        &mut self.expanded
+    }
+
+    pub fn queued(&self) -> Option<&Path> {
+        self.queued.as_deref()
+    }
+
+    pub fn set_queued(&mut self, queued: Option<PathBuf>) {
+        self.queued = queued;
+    }
+
+    pub const fn budget(&self) -> Option<TimeBudget> {
+        self.budget
+    }
+
+    pub const fn set_budget(&mut self, budget: Option<TimeBudget>) {
+        self.budget = budget;
+    }
+
+    pub fn search(&self) -> &str {
+        &self.search
+    }
+
+    pub const fn is_searching(&self) -> bool {
+        self.is_searching
+    }
+
+    pub fn set_search(&mut self, search: String, is_searching: bool) {
+        self.search = search;
+        self.is_searching = is_searching;
+    }
+
+    pub const fn is_table(&self) -> bool {
+        self.is_table
+    }
+
+    pub const fn toggle_table(&mut self) {
+        self.is_table = !self.is_table;
+    }
+
+    pub const fn list_width(&self) -> usize {
+        self.list_width
+    }
+
+    pub const fn set_list_width(&mut self, width: usize) {
+        self.list_width = width;
+    }
+
+    pub fn columns(&self) -> &[(Column, usize)] {
+        &self.columns
+    }
+
+    pub fn set_columns(&mut self, columns: Vec<(Column, usize)>) {
+        self.columns = columns;
+    }
+
+    pub fn jobs(&self) -> impl Iterator<Item = &String> {
+        self.jobs.values()
+    }
+
+    pub fn is_running(&self, job: &'static str) -> bool {
+        self.jobs.contains_key(job)
+    }
+
+    /// Registers a job, returning false if it is already running.
+    pub fn start_job(&mut self, job: &'static str) -> bool {
+        if self.jobs.contains_key(job) {
+            return false;
+        }
+        self.jobs.insert(job, String::new());
+        true
+    }
+
+    pub fn set_progress(&mut self, job: &'static str, progress: String) {
+        self.jobs.insert(job, progress);
+    }
+
+    pub fn finish_job(&mut self, job: &'static str) {
+        self.jobs.remove(job);
+    }
 }
@@ -0,0 +1,132 @@
+use std::path::Path;
+
+use ffmpeg_next::{
+    Packet, Rational, codec, format, frame, media,
+    util::error::{EAGAIN, Error},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::movie::MovieLength;
+
+/// Tolerance in seconds before a file that stops short of its advertised
+/// duration is considered truncated.
+const TRUNCATION_TOLERANCE: u64 = 10;
+
+#[derive(Serialize, Deserialize, Eq, PartialEq, Clone, Hash)]
+pub enum Integrity {
+    Ok,
+    /// The first error found, `position` seconds into the movie
+    Broken {
+        position: u64,
+        error: String,
+    },
+}
+
+/// Whether a frame or packet was received from a decoder, encoder or filter.
+/// One needing more input or having reached the end is not an error, any
+/// other failure is.
+pub const fn received(result: Result<(), Error>) -> Result<bool, Error> {
+    match result {
+        Ok(()) => Ok(true),
+        Err(Error::Other { errno: EAGAIN } | Error::Eof) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+pub struct Verifier;
+
+impl Verifier {
+    /// Demuxes every packet of the files in order and decodes the main video
+    /// stream, stopping at the first error.
+    pub fn verify<'a>(paths: impl IntoIterator<Item = &'a Path>) -> Integrity {
+        let mut offset = 0;
+
+        for path in paths {
+            match Self::verify_file(path) {
+                Ok(length) => offset += length,
+                Err((position, error)) => {
+                    return Integrity::Broken {
+                        position: offset + position,
+                        error,
+                    };
+                }
+            }
+        }
+
+        Integrity::Ok
+    }
+
+    /// Returns the length of the file, or the position and description of
+    /// the first error.
+    fn verify_file(path: &Path) -> Result<u64, (u64, String)> {
+        let mut input = format::input(&path).map_err(|e| (0, e.to_string()))?;
+        let length = MovieLength::from_input(&input);
+
+        let (video_index, time_base, start, mut decoder) = {
+            let stream = input
+                .streams()
+                .best(media::Type::Video)
+                .ok_or_else(|| (0, "No video stream".to_string()))?;
+            let decoder = codec::context::Context::from_parameters(stream.parameters())
+                .and_then(|context| context.decoder().video())
+                .map_err(|e| (0, e.to_string()))?;
+            // Streams without a known start time report AV_NOPTS_VALUE
+            let start = Some(stream.start_time()).filter(|&t| t != i64::MIN);
+            (
+                stream.index(),
+                stream.time_base(),
+                start.unwrap_or(0),
+                decoder,
+            )
+        };
+
+        let mut packet = Packet::empty();
+        let mut frame = frame::Video::empty();
+        let mut position = 0;
+
+        loop {
+            match packet.read(&mut input) {
+                Ok(()) => {}
+                Err(Error::Eof) => break,
+                Err(e) => return Err((position, e.to_string())),
+            }
+
+            if packet.stream() != video_index {
+                continue;
+            }
+
+            if let Some(pts) = packet.pts() {
+                position = Self::seconds(pts.saturating_sub(start), time_base);
+            }
+            if packet.is_corrupt() {
+                return Err((position, "Corrupt packet".to_string()));
+            }
+
+            decoder
+                .send_packet(&packet)
+                .map_err(|e| (position, e.to_string()))?;
+            Self::drain(&mut decoder, &mut frame).map_err(|e| (position, e.to_string()))?;
+        }
+
+        decoder.send_eof().map_err(|e| (position, e.to_string()))?;
+        Self::drain(&mut decoder, &mut frame).map_err(|e| (position, e.to_string()))?;
+
+        if position + TRUNCATION_TOLERANCE < length {
+            return Err((position, "File ends before its duration".to_string()));
+        }
+
+        Ok(length)
+    }
+
+    /// Decodes the frames the decoder has ready.
+    fn drain(decoder: &mut codec::decoder::Video, frame: &mut frame::Video) -> Result<(), Error> {
+        while received(decoder.receive_frame(frame))? {}
+        Ok(())
+    }
+
+    fn seconds(pts: i64, time_base: Rational) -> u64 {
+        (pts * i64::from(time_base.numerator()) / i64::from(time_base.denominator().max(1)))
+            .max(0)
+            .cast_unsigned()
+    }
+}
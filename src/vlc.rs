@@ -0,0 +1,303 @@
+use std::{
+    error::Error,
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde_json::Value;
+
+use crate::{
+    http::{self, Http},
+    player::{PlayRequest, Playback, PlayerBackend, Playing, RemoteSettings},
+};
+
+const STATUS: &str = "/requests/status.json";
+/// Time between two queries of the playback position.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// How long VLC is given to start playing.
+const START_TIMEOUT: Duration = Duration::from_secs(30);
+/// Queries in a row that may fail, e.g. while the network drops for a moment,
+/// before VLC is given up on.
+const MAX_FAILED_POLLS: u32 = 5;
+/// Share of the file past which a stopped playback counts as played to the
+/// end.
+const FINISHED_POSITION: f64 = 0.98;
+
+/// VLC on another device, driven through its HTTP interface.
+pub struct VlcHttp {
+    settings: RemoteSettings,
+    /// Position to seek to once the playback started
+    start: Option<u64>,
+    /// Number of files queued, only the last of which can finish the movie
+    parts: usize,
+}
+
+impl VlcHttp {
+    pub fn new(settings: &RemoteSettings) -> Self {
+        Self {
+            settings: settings.clone(),
+            start: None,
+            parts: 1,
+        }
+    }
+
+    /// Sends a command, if any, and returns the status of the player.
+    fn status(&self, command: &str) -> Result<Value, Box<dyn Error>> {
+        let settings = &self.settings;
+        let http = Http::new(
+            &settings.host,
+            settings.port,
+            &settings.username,
+            &settings.password,
+        );
+        let target = if command.is_empty() {
+            STATUS.to_string()
+        } else {
+            format!("{STATUS}?command={command}")
+        };
+        http.get(&target)
+    }
+}
+
+impl PlayerBackend for VlcHttp {
+    /// Plays the first file and queues the other parts after it.
+    fn play(&self, request: &PlayRequest) -> Result<Box<dyn Playing>, Box<dyn Error>> {
+        let mut files = request
+            .files
+            .iter()
+            .map(|file| http::percent_encode(&self.settings.remote_path(file)));
+        let first = files.next().ok_or("Nothing to play")?;
+
+        self.status(&format!("in_play&input={first}"))?;
+        for file in files {
+            self.status(&format!("in_enqueue&input={file}"))?;
+        }
+
+        Ok(Box::new(Self {
+            settings: self.settings.clone(),
+            start: request.start,
+            parts: request.files.len(),
+        }))
+    }
+}
+
+impl Playing for VlcHttp {
+    /// Polls the position until VLC stops. If it cannot be reached anymore,
+    /// the last position known is kept.
+    fn wait(
+        mut self: Box<Self>,
+        progress: &mut dyn FnMut(u64),
+    ) -> Result<Option<Playback>, Box<dyn Error>> {
+        let started = Instant::now();
+        let mut playback: Option<Playback> = None;
+        // Playlist item being played and its length, and the number and
+        // length of the items played before it
+        let mut current: Option<(i64, u64)> = None;
+        let mut done = 0;
+        let mut played = 0;
+        let mut failures = 0;
+
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let status = match self.status("") {
+                Ok(status) => {
+                    failures = 0;
+                    status
+                }
+                Err(_) if failures < MAX_FAILED_POLLS => {
+                    failures += 1;
+                    continue;
+                }
+                Err(_) if playback.is_some() => break,
+                Err(e) => return Err(e),
+            };
+            let playing = matches!(status["state"].as_str(), Some("playing" | "paused"));
+
+            match (playing, &mut playback) {
+                (true, playback) => {
+                    if let Some(start) = self.start.take() {
+                        self.status(&format!("seek&val={start}"))?;
+                    }
+                    // The time is counted from the start of the current item
+                    let item = status["currentplid"].as_i64().unwrap_or(-1);
+                    if let Some((previous, length)) = current
+                        && previous != item
+                    {
+                        done += 1;
+                        played += length;
+                    }
+                    current = Some((item, status["length"].as_u64().unwrap_or(0)));
+                    let position = played + status["time"].as_u64().unwrap_or(0);
+                    if playback.as_ref().is_none_or(|p| p.position != position) {
+                        progress(position);
+                    }
+                    *playback = Some(Playback {
+                        position,
+                        finished: done + 1 >= self.parts
+                            && status["position"].as_f64().unwrap_or(0.0) >= FINISHED_POSITION,
+                    });
+                }
+                (false, Some(_)) => break,
+                (false, None) if started.elapsed() > START_TIMEOUT => {
+                    return Err("VLC did not start playing".into());
+                }
+                (false, None) => {}
+            }
+        }
+
+        Ok(playback)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::{http::mock, tracks::TrackChoices};
+
+    fn vlc(port: u16) -> VlcHttp {
+        VlcHttp::new(&RemoteSettings {
+            host: "127.0.0.1".to_string(),
+            port,
+            password: "secret".to_string(),
+            path_map: [(PathBuf::from("/mnt/nas"), "/media".to_string())].into(),
+            ..RemoteSettings::default()
+        })
+    }
+
+    fn target(request: &str) -> &str {
+        request.split_whitespace().nth(1).unwrap()
+    }
+
+    #[test]
+    fn play_queues_the_other_parts() {
+        let status = json!({ "state": "stopped" }).to_string();
+        let (port, server) = mock::serve(vec![(200, status.clone()), (200, status)]);
+        let files = [
+            PathBuf::from("/mnt/nas/A Movie/cd1.avi"),
+            PathBuf::from("/mnt/nas/A Movie/cd2.avi"),
+        ];
+
+        vlc(port)
+            .play(&PlayRequest {
+                path: files[0].clone(),
+                files: files.to_vec(),
+                start: None,
+                tracks: TrackChoices::default(),
+                disc: None,
+            })
+            .unwrap();
+
+        let requests = server.join().unwrap();
+        assert_eq!(
+            target(&requests[0]),
+            "/requests/status.json?command=in_play&input=%2Fmedia%2FA%20Movie%2Fcd1.avi"
+        );
+        assert_eq!(
+            target(&requests[1]),
+            "/requests/status.json?command=in_enqueue&input=%2Fmedia%2FA%20Movie%2Fcd2.avi"
+        );
+    }
+
+    #[test]
+    fn play_reports_errors() {
+        let (port, _server) = mock::serve(vec![(401, String::new())]);
+        let files = [PathBuf::from("/mnt/nas/Movie.mkv")];
+
+        let result = vlc(port).play(&PlayRequest {
+            path: files[0].clone(),
+            files: files.to_vec(),
+            start: None,
+            tracks: TrackChoices::default(),
+            disc: None,
+        });
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn wait_seeks_and_polls_until_vlc_stops() {
+        let playing = json!({ "state": "playing", "time": 600, "position": 0.5 });
+        let (port, server) = mock::serve(vec![
+            (200, playing.to_string()),
+            (200, playing.to_string()),
+            (200, json!({ "state": "stopped" }).to_string()),
+        ]);
+        let playing = Box::new(VlcHttp {
+            start: Some(590),
+            ..vlc(port)
+        });
+
+        let mut positions = Vec::new();
+        let playback = playing
+            .wait(&mut |position| positions.push(position))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(positions, [600]);
+        assert_eq!(playback.position, 600);
+        assert!(!playback.finished);
+        let requests = server.join().unwrap();
+        assert_eq!(
+            target(&requests[1]),
+            "/requests/status.json?command=seek&val=590"
+        );
+    }
+
+    #[test]
+    fn wait_counts_the_parts_already_played() {
+        let part = |id, time: u32, length: u32| {
+            json!({
+                "state": "playing",
+                "currentplid": id,
+                "time": time,
+                "length": length,
+                "position": f64::from(time) / f64::from(length),
+            })
+            .to_string()
+        };
+        let (port, _server) = mock::serve(vec![
+            (200, part(3, 100, 1000)),
+            (200, part(4, 50, 900)),
+            (200, part(4, 890, 900)),
+            (200, json!({ "state": "stopped" }).to_string()),
+        ]);
+
+        let playing = Box::new(VlcHttp {
+            parts: 2,
+            ..vlc(port)
+        });
+
+        let mut positions = Vec::new();
+        let playback = playing
+            .wait(&mut |position| positions.push(position))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(positions, [100, 1050, 1890]);
+        assert_eq!(playback.position, 1890);
+        assert!(playback.finished);
+    }
+    #[test]
+    fn wait_goes_on_after_a_failed_poll() {
+        let playing = |time: u32| json!({ "state": "playing", "time": time }).to_string();
+        let (port, _server) = mock::serve(vec![
+            (200, playing(600)),
+            (500, String::new()),
+            (200, playing(602)),
+            (200, json!({ "state": "stopped" }).to_string()),
+        ]);
+
+        let mut positions = Vec::new();
+        let playback = Box::new(vlc(port))
+            .wait(&mut |position| positions.push(position))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(positions, [600, 602]);
+        assert_eq!(playback.position, 602);
+    }
+}
@@ -0,0 +1,261 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::config::Config;
+
+/// Per-round constants of MD5, from the sine of the round number.
+const CONSTANTS: [u32; 64] = [
+    0xd76a_a478,
+    0xe8c7_b756,
+    0x2420_70db,
+    0xc1bd_ceee,
+    0xf57c_0faf,
+    0x4787_c62a,
+    0xa830_4613,
+    0xfd46_9501,
+    0x6980_98d8,
+    0x8b44_f7af,
+    0xffff_5bb1,
+    0x895c_d7be,
+    0x6b90_1122,
+    0xfd98_7193,
+    0xa679_438e,
+    0x49b4_0821,
+    0xf61e_2562,
+    0xc040_b340,
+    0x265e_5a51,
+    0xe9b6_c7aa,
+    0xd62f_105d,
+    0x0244_1453,
+    0xd8a1_e681,
+    0xe7d3_fbc8,
+    0x21e1_cde6,
+    0xc337_07d6,
+    0xf4d5_0d87,
+    0x455a_14ed,
+    0xa9e3_e905,
+    0xfcef_a3f8,
+    0x676f_02d9,
+    0x8d2a_4c8a,
+    0xfffa_3942,
+    0x8771_f681,
+    0x6d9d_6122,
+    0xfde5_380c,
+    0xa4be_ea44,
+    0x4bde_cfa9,
+    0xf6bb_4b60,
+    0xbebf_bc70,
+    0x289b_7ec6,
+    0xeaa1_27fa,
+    0xd4ef_3085,
+    0x0488_1d05,
+    0xd9d4_d039,
+    0xe6db_99e5,
+    0x1fa2_7cf8,
+    0xc4ac_5665,
+    0xf429_2244,
+    0x432a_ff97,
+    0xab94_23a7,
+    0xfc93_a039,
+    0x655b_59c3,
+    0x8f0c_cc92,
+    0xffef_f47d,
+    0x8584_5dd1,
+    0x6fa8_7e4f,
+    0xfe2c_e6e0,
+    0xa301_4314,
+    0x4e08_11a1,
+    0xf753_7e82,
+    0xbd3a_f235,
+    0x2ad7_d2bb,
+    0xeb86_d391,
+];
+
+/// Per-round shift amounts of MD5.
+const SHIFTS: [u32; 64] = [
+    7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9,
+    14, 20, 5, 9, 14, 20, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 6, 10, 15,
+    21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+];
+
+/// A position mpv saved when it was quit.
+struct Saved {
+    position: u64,
+    /// When the file was written, to tell whether it is newer than the
+    /// position cineteca saved
+    modified: SystemTime,
+}
+
+/// Resume positions written by mpv to its `watch_later` directory, in files
+/// named after the MD5 of the played path.
+pub struct WatchLater {
+    saved: HashMap<String, Saved>,
+}
+
+impl WatchLater {
+    /// Reads the positions from the current and the legacy location of the
+    /// directory. Missing or unreadable files are skipped.
+    pub fn load() -> Self {
+        let dirs = [
+            Config::xdg_base("XDG_STATE_HOME", ".local/state"),
+            Config::xdg_base("XDG_CONFIG_HOME", ".config"),
+        ];
+        Self::from_dirs(
+            dirs.into_iter()
+                .flatten()
+                .map(|dir| dir.join("mpv").join("watch_later")),
+        )
+    }
+
+    /// Reads the positions saved in `dirs`.
+    fn from_dirs(dirs: impl Iterator<Item = PathBuf>) -> Self {
+        let saved = dirs
+            .filter_map(|dir| fs::read_dir(dir).ok())
+            .flatten()
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let name = entry.file_name().to_str()?.to_owned();
+                Some((name, Self::read(&entry.path())?))
+            })
+            .collect();
+
+        Self { saved }
+    }
+
+    /// Position saved for `path`, if it was saved after `after`.
+    pub fn position(&self, path: &Path, after: Option<SystemTime>) -> Option<u64> {
+        let key = format!(
+            "{:032X}",
+            u128::from_be_bytes(md5(path.to_string_lossy().as_bytes()))
+        );
+        self.saved
+            .get(&key)
+            .filter(|saved| after.is_none_or(|after| saved.modified > after))
+            .map(|saved| saved.position)
+    }
+
+    fn read(path: &Path) -> Option<Saved> {
+        let content = fs::read_to_string(path).ok()?;
+        let start = content
+            .lines()
+            .find_map(|line| line.strip_prefix("start="))?
+            .parse()
+            .ok()?;
+
+        Some(Saved {
+            position: Duration::try_from_secs_f64(start).ok()?.as_secs(),
+            modified: fs::metadata(path).and_then(|m| m.modified()).ok()?,
+        })
+    }
+}
+
+/// MD5 digest of `data`, as mpv names its files with it.
+fn md5(data: &[u8]) -> [u8; 16] {
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(data.len() as u64).wrapping_mul(8).to_le_bytes());
+
+    let mut state: [u32; 4] = [0x6745_2301, 0xefcd_ab89, 0x98ba_dcfe, 0x1032_5476];
+    for chunk in message.chunks_exact(64) {
+        let words: Vec<u32> = chunk
+            .chunks_exact(4)
+            .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+            .collect();
+        let [mut a, mut b, mut c, mut d] = state;
+
+        for i in 0..64 {
+            let (mixed, word) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let rotated = a
+                .wrapping_add(mixed)
+                .wrapping_add(CONSTANTS[i])
+                .wrapping_add(words[word])
+                .rotate_left(SHIFTS[i]);
+            (a, b, c, d) = (d, b.wrapping_add(rotated), b, c);
+        }
+
+        for (value, added) in state.iter_mut().zip([a, b, c, d]) {
+            *value = value.wrapping_add(added);
+        }
+    }
+
+    let mut digest = [0; 16];
+    for (bytes, value) in digest.chunks_exact_mut(4).zip(state) {
+        bytes.copy_from_slice(&value.to_le_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    fn hex(digest: [u8; 16]) -> String {
+        format!("{:032x}", u128::from_be_bytes(digest))
+    }
+
+    #[test]
+    fn md5_matches_the_rfc_1321_test_suite() {
+        let suite = [
+            ("", "d41d8cd98f00b204e9800998ecf8427e"),
+            ("a", "0cc175b9c0f1b6a831c399e269772661"),
+            ("abc", "900150983cd24fb0d6963f7d28e17f72"),
+            ("message digest", "f96b697d7cb7938d525a2f31aaf161d0"),
+            (
+                "abcdefghijklmnopqrstuvwxyz",
+                "c3fcd3d76192e4007dfb496cca67e13b",
+            ),
+            (
+                "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789",
+                "d174ab98d277d9f5a5611c2c9f419d9f",
+            ),
+            (
+                "12345678901234567890123456789012345678901234567890123456789012345678901234567890",
+                "57edf4a22be3c955ac49da2e2107b67a",
+            ),
+        ];
+        for (data, digest) in suite {
+            assert_eq!(hex(md5(data.as_bytes())), digest, "MD5 of {data:?}");
+        }
+    }
+
+    #[test]
+    fn position_is_read_from_the_file_named_after_the_path() {
+        let dir = env::temp_dir().join(format!("cineteca-test-{}-watch_later", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("BD5AFF7C5B22590DEA6DC5B70C26F54A"),
+            "# /movies/Alien.mkv\nstart=3605.250000\nvolume=80\n",
+        )
+        .unwrap();
+
+        let watch_later = WatchLater::from_dirs(std::iter::once(dir.clone()));
+        let path = Path::new("/movies/Alien.mkv");
+
+        assert_eq!(watch_later.position(path, None), Some(3605));
+        assert_eq!(
+            watch_later.position(path, Some(SystemTime::UNIX_EPOCH)),
+            Some(3605)
+        );
+        let saved_since = SystemTime::now() + Duration::from_secs(60);
+        assert_eq!(watch_later.position(path, Some(saved_since)), None);
+        assert_eq!(
+            watch_later.position(Path::new("/movies/Up.mkv"), None),
+            None
+        );
+        fs::remove_dir_all(dir).ok();
+    }
+}